@@ -1,4 +1,4 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use parsecs::{izip, system, world::World};
 use std::time::Duration;
 
@@ -29,7 +29,7 @@ fn reinsertion(c: &mut Criterion) {
 }
 
 #[derive(Default)]
-struct Position(f32, f32);
+struct Position(f32);
 
 #[derive(Default)]
 struct Health(u8);
@@ -51,6 +51,20 @@ fn component_insertion(c: &mut Criterion) {
 	});
 }
 
+fn component_batch_insertion(c: &mut Criterion) {
+	c.bench_function("spawning 1 million entities with a component batch", |b| {
+		let number_of_entities = 1_000_000;
+		b.iter_batched(
+			World::new,
+			|mut world| {
+				world.spawn_batch((0..number_of_entities).map(|_| (Position::default(),))).unwrap();
+				world
+			},
+			BatchSize::LargeInput,
+		)
+	});
+}
+
 fn component_removal(c: &mut Criterion) {
 	c.bench_function("removing 1 million components", |b| {
 		let mut world = World::new();
@@ -95,7 +109,7 @@ fn complex_entities(c: &mut Criterion) {
 			world.add_component(*entity, Name("Test Component".to_string())).unwrap();
 		}
 		b.iter(|| {
-			translation_system(&mut world).expect("Failed to execute translation system!");
+			translation_system(&world).expect("Failed to execute translation system!");
 		})
 	});
 }
@@ -136,6 +150,7 @@ criterion_group!(
 		removal,
 		reinsertion,
 		component_insertion,
+		component_batch_insertion,
 		component_removal,
 		component_mutation,
 		complex_entities,
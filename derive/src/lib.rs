@@ -0,0 +1,77 @@
+#![forbid(unsafe_code)]
+#![forbid(clippy::all, clippy::nursery, clippy::cargo)]
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, parse_macro_input};
+
+/// Implement `parsecs::reflect::Reflect` for a struct with named fields, exposing every field
+/// except those marked `#[reflect(skip)]`. Every exposed field must implement `Reflect` itself.
+#[proc_macro_derive(Reflect, attributes(reflect))]
+pub fn derive_reflect(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	expand_reflect(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand_reflect(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+	let Data::Struct(data) = &input.data else {
+		return Err(syn::Error::new_spanned(input, "Reflect can only be derived for structs"));
+	};
+	let Fields::Named(fields) = &data.fields else {
+		return Err(syn::Error::new_spanned(input, "Reflect can only be derived for structs with named fields"));
+	};
+
+	let mut exposed = Vec::new();
+	for field in fields.named.iter() {
+		if !is_skipped(field)? {
+			exposed.extend(field.ident.clone());
+		}
+	}
+	let names = exposed.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+	let name = &input.ident;
+	let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+	Ok(quote! {
+		impl #impl_generics ::parsecs::reflect::Reflect for #name #type_generics #where_clause {
+			fn field_names(&self) -> &'static [&'static str] {
+				&[#(#names),*]
+			}
+
+			fn field(&self, name: &str) -> Option<&dyn ::parsecs::reflect::Reflect> {
+				match name {
+					#(#names => Some(&self.#exposed),)*
+					_ => None,
+				}
+			}
+
+			fn field_mut(&mut self, name: &str) -> Option<&mut dyn ::parsecs::reflect::Reflect> {
+				match name {
+					#(#names => Some(&mut self.#exposed),)*
+					_ => None,
+				}
+			}
+
+			fn to_value(&self) -> ::parsecs::error::Result<::parsecs::reflect::Value> {
+				::parsecs::reflect::fields_to_value(self)
+			}
+
+			fn set_value(&mut self, value: ::parsecs::reflect::Value) -> ::parsecs::error::Result<()> {
+				::parsecs::reflect::set_fields(self, value)
+			}
+		}
+	})
+}
+
+fn is_skipped(field: &syn::Field) -> syn::Result<bool> {
+	let mut skipped = false;
+	for attribute in field.attrs.iter().filter(|attribute| attribute.path().is_ident("reflect")) {
+		attribute.parse_nested_meta(|meta| {
+			if meta.path.is_ident("skip") {
+				skipped = true;
+				return Ok(());
+			}
+			Err(meta.error("expected `skip`"))
+		})?;
+	}
+	Ok(skipped)
+}
@@ -1,38 +1,48 @@
 use anyhow::Result;
 use kiss3d::{camera::ArcBall, light::Light, scene::SceneNode, window::Window};
 use nalgebra::{Point3, UnitQuaternion, Vector3};
-use parsecs::{system, world::World};
+use parsecs::{
+	app::{App, Runner},
+	local::{with_local, FromWorld, Local},
+	system,
+	time::Time,
+	world::World,
+};
 use rand::Rng;
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::time::Duration;
 
 fn main() -> Result<()> {
 	let mut window = Window::new("Entity-Component-System Architecture Demo");
 	window.set_light(Light::StickToCamera);
 
-	let mut world = create_world(&mut window);
+	let mut app = App::new();
+	populate_world(app.world_mut(), &mut window);
 
-	let mut arc_ball = {
-		let eye = Point3::new(10.0, 10.0, 10.0);
-		let at = Point3::origin();
-		ArcBall::new(eye, at)
-	};
-
-	let start = Instant::now();
-	let color_system = ColorSystem::new();
-	while window.render_with_camera(&mut arc_ball) {
-		rotation_system(0.014, &mut world)?;
-		let now = Instant::now();
-		let elapsed = now.duration_since(start).as_secs_f32();
-		scaling_system(elapsed, &mut world)?;
-		color_system.run(&mut world)?;
-	}
+	app.add_fixed_system(|world: &World| rotation_system(0.014, world).map_err(Into::into))
+		.add_system(|world: &World| {
+			let elapsed = world.resources().borrow().get::<Time>().map(Time::elapsed_seconds).unwrap_or_default();
+			scaling_system(elapsed, world).map_err(Into::into)
+		})
+		.add_system(with_local(|start_time: &mut Local<StartTime>, world: &World| {
+			color_system(start_time, world).map_err(Into::into)
+		}))
+		.set_runner(Runner::Custom(Box::new(move |app| {
+			let mut arc_ball = {
+				let eye = Point3::new(10.0, 10.0, 10.0);
+				let at = Point3::origin();
+				ArcBall::new(eye, at)
+			};
+			while window.render_with_camera(&mut arc_ball) {
+				app.update()?;
+			}
+			Ok(())
+		})));
 
-	Ok(())
+	app.run().map_err(|error| anyhow::anyhow!(error.to_string()))
 }
 
-fn create_world(window: &mut Window) -> World {
+fn populate_world(world: &mut World, window: &mut Window) {
 	let mut rng = rand::thread_rng();
-	let mut world = World::new();
 	let entities = world.create_entities(10);
 	for entity in entities {
 		let mut node = window.add_cube(1.0, 1.0, 1.0);
@@ -41,7 +51,6 @@ fn create_world(window: &mut Window) -> World {
 		node.set_local_translation([rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0)].into());
 		world.add_component(entity, node).unwrap();
 	}
-	world
 }
 
 // Using the `system!` macro
@@ -51,7 +60,7 @@ system!(rotation_system, [_resources, _entity], (value: f32), (node: SceneNode)
 });
 
 // Using a plain function
-pub fn scaling_system(value: f32, world: &mut World) -> Result<()> {
+pub fn scaling_system(value: f32, world: &World) -> Result<()> {
 	world
 		.get_component_vec_mut::<SceneNode>()
 		.unwrap_or_else(|| panic!("System accessed an unregistered component type: {:?}", stringify!(SceneNode)))
@@ -71,22 +80,23 @@ pub fn scaling_system(value: f32, world: &mut World) -> Result<()> {
 		})
 }
 
-// Encapsulating the system in a struct
-// to persist system state across calls
-struct ColorSystem {
-	start_time: Duration,
+// Keeping system state across calls in a `Local`,
+// created from the world on the first run
+struct StartTime {
+	elapsed: Duration,
 }
 
-impl ColorSystem {
-	pub fn new() -> Self {
+impl FromWorld for StartTime {
+	fn from_world(world: &World) -> Self {
 		Self {
-			start_time: SystemTime::now().duration_since(UNIX_EPOCH).unwrap(),
+			elapsed: world.resources().borrow().get::<Time>().map(Time::elapsed).unwrap_or_default(),
 		}
 	}
-
-	system!(run, [_resources, _entity], (self: &Self), (node: SceneNode) -> Result<()> {
-		let time = (SystemTime::now().duration_since(UNIX_EPOCH).unwrap() - self.start_time).as_secs_f32();
-		node.set_color(time.sin(), time.cos(), 0.5);
-		Ok(())
-	});
 }
+
+system!(color_system, [resources, _entity], (start_time: &mut Local<StartTime>), (node: SceneNode) -> Result<()> {
+	let elapsed = resources.borrow().get::<Time>().map(Time::elapsed).unwrap_or_default();
+	let time = (elapsed - start_time.elapsed).as_secs_f32();
+	node.set_color(time.sin(), time.cos(), 0.5);
+	Ok(())
+});
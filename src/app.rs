@@ -0,0 +1,325 @@
+use crate::{
+	diagnostics::Diagnostics,
+	error::Result,
+	schedule::{error::SystemError, ErrorHandler, ErrorPolicy, ExclusiveSystem, Schedule, System},
+	state::{StateDriver, StateHook, StateTransitions, States},
+	time::{FixedTime, Time},
+	world::World,
+};
+use std::{any::TypeId, collections::HashSet, rc::Rc, time::Instant};
+
+pub type RunnerFn = Box<dyn FnOnce(&mut App) -> Result<()>>;
+
+/// A bundle of setup, such as component registrations, resources and systems, that is added to an
+/// `App` in one call.
+pub trait Plugin: 'static {
+	fn build(&self, app: &mut App);
+}
+
+/// Decides how `App::run` drives the schedule.
+pub enum Runner {
+	/// Run the schedule a fixed number of times, for headless simulations and tests.
+	Iterations(usize),
+
+	/// Run the schedule until the predicate returns true. It is checked before every update.
+	Until(Box<dyn FnMut(&World) -> bool>),
+
+	/// Hand the app to a user supplied loop, for example a window's render loop, which calls
+	/// `App::update` itself.
+	Custom(RunnerFn),
+}
+
+impl Default for Runner {
+	fn default() -> Self {
+		Self::Iterations(1)
+	}
+}
+
+/// Owns a `World`, the schedule of systems that update it and the runner that drives them.
+///
+/// Every update advances the `Time` resource, applies queued state transitions, runs the fixed
+/// timestep schedule as many times as the `FixedTime` resource allows and then runs the main
+/// schedule.
+pub struct App {
+	world: World,
+	schedule: Schedule,
+	fixed_schedule: Schedule,
+	runner: Runner,
+	plugins: HashSet<TypeId>,
+	states: Vec<Box<dyn StateDriver>>,
+}
+
+impl Default for App {
+	fn default() -> Self {
+		let world = World::new();
+		{
+			let mut resources = world.resources().borrow_mut();
+			resources.insert(Time::new());
+			resources.insert(FixedTime::default());
+		}
+		Self {
+			world,
+			schedule: Schedule::new(),
+			fixed_schedule: Schedule::new(),
+			runner: Runner::default(),
+			plugins: HashSet::new(),
+			states: Vec::new(),
+		}
+	}
+}
+
+impl App {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub const fn world(&self) -> &World {
+		&self.world
+	}
+
+	pub const fn world_mut(&mut self) -> &mut World {
+		&mut self.world
+	}
+
+	/// Build `plugin` into the app. Adding a plugin type that was already added does nothing.
+	pub fn add_plugin<P: Plugin>(&mut self, plugin: P) -> &mut Self {
+		if self.plugins.insert(TypeId::of::<P>()) {
+			plugin.build(self);
+		}
+		self
+	}
+
+	pub fn has_plugin<P: Plugin>(&self) -> bool {
+		self.plugins.contains(&TypeId::of::<P>())
+	}
+
+	pub fn add_system(&mut self, system: impl System + 'static) -> &mut Self {
+		self.schedule.add_system(system);
+		self
+	}
+
+	/// Add a system with mutable access to the world. Commands queued by the systems before it are
+	/// applied before it runs.
+	pub fn add_exclusive_system(&mut self, system: impl ExclusiveSystem + 'static) -> &mut Self {
+		self.schedule.add_exclusive_system(system);
+		self
+	}
+
+	/// Add a system to the fixed timestep schedule, which runs zero or more times per update in
+	/// steps of `FixedTime::step`.
+	pub fn add_fixed_system(&mut self, system: impl System + 'static) -> &mut Self {
+		self.fixed_schedule.add_system(system);
+		self
+	}
+
+	/// Track a state of type `S`, starting in `initial`. Transitions queued with `NextState` are
+	/// applied at the start of every update. Adding a state type twice resets it to `initial`.
+	pub fn add_state<S: States>(&mut self, initial: S) -> &mut Self {
+		self.world.init_state(initial);
+		if self.state_transitions::<S>().is_none() {
+			self.states.push(Box::<StateTransitions<S>>::default());
+		}
+		self
+	}
+
+	/// Add a system that runs on an `OnEnter`, `OnExit` or `OnTransition` of a state added with
+	/// `add_state`. Panics if the state type was not added.
+	pub fn add_state_system<S: States>(&mut self, hook: impl Into<StateHook<S>>, system: impl System + 'static) -> &mut Self {
+		self.state_transitions::<S>()
+			.unwrap_or_else(|| panic!("State {} was not added to the app", std::any::type_name::<S>()))
+			.add_system(hook.into(), system);
+		self
+	}
+
+	fn state_transitions<S: States>(&mut self) -> Option<&mut StateTransitions<S>> {
+		self.states.iter_mut().find_map(|driver| driver.as_any_mut().downcast_mut::<StateTransitions<S>>())
+	}
+
+	/// Set the error policy of the main and fixed timestep schedules, for systems that do not have
+	/// their own.
+	pub const fn set_error_policy(&mut self, error_policy: ErrorPolicy) -> &mut Self {
+		self.schedule.set_error_policy(error_policy);
+		self.fixed_schedule.set_error_policy(error_policy);
+		self
+	}
+
+	/// Set the error handler of the main and fixed timestep schedules, see
+	/// `Schedule::set_error_handler`.
+	pub fn set_error_handler(&mut self, error_handler: impl Fn(&SystemError) + 'static) -> &mut Self {
+		let error_handler: ErrorHandler = Rc::new(error_handler);
+		self.schedule.set_shared_error_handler(error_handler.clone());
+		self.fixed_schedule.set_shared_error_handler(error_handler);
+		self
+	}
+
+	/// Catch panics in the systems of the main and fixed timestep schedules, see
+	/// `Schedule::set_catch_panics`.
+	pub const fn set_catch_panics(&mut self, catch_panics: bool) -> &mut Self {
+		self.schedule.set_catch_panics(catch_panics);
+		self.fixed_schedule.set_catch_panics(catch_panics);
+		self
+	}
+
+	pub fn register_component<T: 'static>(&mut self) -> &mut Self {
+		self.world.register_component::<T>();
+		self
+	}
+
+	pub fn insert_resource<T: 'static>(&mut self, resource: T) -> &mut Self {
+		self.world.resources().borrow_mut().insert(resource);
+		self
+	}
+
+	pub fn set_runner(&mut self, runner: Runner) -> &mut Self {
+		self.runner = runner;
+		self
+	}
+
+	/// Advance time, apply queued state transitions, run the fixed timestep schedule for every
+	/// elapsed step and then run the main schedule once. The duration of the update is recorded in
+	/// the `Diagnostics` resource if there is one.
+	pub fn update(&mut self) -> Result<()> {
+		let start = Instant::now();
+		trace_span!("update");
+		let steps = {
+			let mut resources = self.world.resources().borrow_mut();
+			let delta = resources.get_mut::<Time>().map(Time::update).unwrap_or_default();
+			resources.get_mut::<FixedTime>().map(|fixed_time| fixed_time.accumulate(delta)).unwrap_or_default()
+		};
+		{
+			trace_span!("stage", name = "state_transitions");
+			for driver in self.states.iter_mut() {
+				driver.apply(&mut self.world)?;
+			}
+		}
+		{
+			trace_span!("stage", name = "fixed_update", steps);
+			for _ in 0..steps {
+				self.fixed_schedule.run(&mut self.world)?;
+			}
+		}
+		{
+			trace_span!("stage", name = "update");
+			self.schedule.run(&mut self.world)?;
+		}
+		if let Some(diagnostics) = self.world.resources().borrow_mut().get_mut_untracked::<Diagnostics>() {
+			diagnostics.record_frame(start.elapsed());
+		}
+		Ok(())
+	}
+
+	/// Drive the schedule with the app's runner, stopping at the first error.
+	pub fn run(&mut self) -> Result<()> {
+		match std::mem::take(&mut self.runner) {
+			Runner::Iterations(iterations) => (0..iterations).try_for_each(|_| self.update()),
+			Runner::Until(mut done) => {
+				while !done(&self.world) {
+					self.update()?;
+				}
+				Ok(())
+			},
+			Runner::Custom(runner) => runner(self),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		condition::{resource_changed, SystemExt},
+		state::NextState,
+	};
+	use std::time::Duration;
+
+	#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+	struct Ticks(u32);
+
+	fn tick(world: &World) -> Result<()> {
+		world.resources().borrow_mut().get_mut::<Ticks>().unwrap().0 += 1;
+		Ok(())
+	}
+
+	fn ticks(app: &App) -> u32 {
+		app.world().resources().borrow().get::<Ticks>().unwrap().0
+	}
+
+	struct TickPlugin;
+
+	impl Plugin for TickPlugin {
+		fn build(&self, app: &mut App) {
+			app.insert_resource(Ticks::default()).add_system(tick);
+		}
+	}
+
+	#[test]
+	fn plugins_are_built_once() -> Result<()> {
+		let mut app = App::new();
+		app.add_plugin(TickPlugin).add_plugin(TickPlugin);
+		assert!(app.has_plugin::<TickPlugin>());
+
+		app.set_runner(Runner::Iterations(3)).run()?;
+		assert_eq!(ticks(&app), 3);
+
+		Ok(())
+	}
+
+	#[test]
+	fn fixed_timestep() -> Result<()> {
+		let mut app = App::new();
+		app.insert_resource(Time::manual(Duration::from_millis(25)))
+			.insert_resource(FixedTime::new(Duration::from_millis(10)))
+			.insert_resource(Ticks::default())
+			.add_fixed_system(tick)
+			.set_runner(Runner::Iterations(4))
+			.run()?;
+
+		assert_eq!(ticks(&app), 10);
+		let resources = app.world().resources().borrow();
+		assert_eq!(resources.get::<Time>().unwrap().frame_count(), 4);
+		assert_eq!(resources.get::<FixedTime>().unwrap().alpha(), 0.0);
+
+		Ok(())
+	}
+
+	#[test]
+	fn untouched_resources_are_unchanged() -> Result<()> {
+		#[derive(Debug, Clone, PartialEq, Eq)]
+		struct Level(u32);
+
+		let mut app = App::new();
+		app.insert_resource(Ticks::default())
+			.insert_resource(Diagnostics::new())
+			.add_state(Level(1))
+			.add_system(tick.run_if(resource_changed::<NextState<Level>>()))
+			.add_system(tick.run_if(resource_changed::<Diagnostics>()))
+			.set_runner(Runner::Iterations(4))
+			.run()?;
+
+		// Both systems only run on the first frame, when the resources were inserted.
+		assert_eq!(ticks(&app), 2);
+
+		Ok(())
+	}
+
+	#[test]
+	fn runners() -> Result<()> {
+		let mut app = App::new();
+		app.add_plugin(TickPlugin);
+
+		app.set_runner(Runner::Until(Box::new(|world| world.resources().borrow().get::<Ticks>() == Some(&Ticks(5)))))
+			.run()?;
+		assert_eq!(ticks(&app), 5);
+
+		app.set_runner(Runner::Custom(Box::new(|app| {
+			while ticks(app) < 7 {
+				app.update()?;
+			}
+			Ok(())
+		})))
+		.run()?;
+		assert_eq!(ticks(&app), 7);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,232 @@
+use self::error::DuplicateComponentError;
+use crate::{
+	error::Result,
+	name::Name,
+	stable_id::StableId,
+	world::{Component, ComponentMap, Entity, World},
+};
+use std::any::TypeId;
+
+pub mod error {
+	#[derive(Debug)]
+	pub struct DuplicateComponentError {
+		pub type_name: &'static str,
+	}
+
+	impl std::error::Error for DuplicateComponentError {}
+
+	impl std::fmt::Display for DuplicateComponentError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Component '{}' appears more than once in a bundle.", self.type_name)
+		}
+	}
+}
+
+/// A group of components that are added to an entity together, implemented for tuples of up to
+/// eight components.
+pub trait Bundle: Sized + 'static {
+	fn type_ids() -> Vec<(TypeId, &'static str)>;
+
+	fn register(world: &mut World);
+
+	/// Box every component of the bundle.
+	fn into_components(self) -> Vec<(TypeId, Component)>;
+
+	/// Insert one bundle per entity, borrowing and growing each component column once.
+	/// Every column of the bundle must already be registered.
+	fn insert_batch(components: &ComponentMap, entities: &[Entity], bundles: Vec<Self>) -> Result<()>;
+}
+
+macro_rules! impl_bundle {
+	($(($component:ident, $value:ident, $column:ident)),*) => {
+		impl<$($component: 'static),*> Bundle for ($($component,)*) {
+			fn type_ids() -> Vec<(TypeId, &'static str)> {
+				vec![$((TypeId::of::<$component>(), std::any::type_name::<$component>())),*]
+			}
+
+			fn register(world: &mut World) {
+				$(world.register_component::<$component>();)*
+			}
+
+			fn into_components(self) -> Vec<(TypeId, Component)> {
+				let ($($value,)*) = self;
+				vec![$((TypeId::of::<$component>(), Box::new($value) as Component)),*]
+			}
+
+			fn insert_batch(components: &ComponentMap, entities: &[Entity], bundles: Vec<Self>) -> Result<()> {
+				let length = entities.iter().map(|entity| *entity.index() as usize + 1).max().unwrap_or_default();
+				$(
+					let mut $column = components[&TypeId::of::<$component>()].borrow_mut();
+					$column.grow_to(length);
+				)*
+				for (entity, ($($value,)*)) in entities.iter().zip(bundles) {
+					$($column.insert(*entity, Box::new($value))?;)*
+				}
+				Ok(())
+			}
+		}
+	};
+}
+
+impl_bundle!((A, a, column_a));
+impl_bundle!((A, a, column_a), (B, b, column_b));
+impl_bundle!((A, a, column_a), (B, b, column_b), (C, c, column_c));
+impl_bundle!((A, a, column_a), (B, b, column_b), (C, c, column_c), (D, d, column_d));
+impl_bundle!((A, a, column_a), (B, b, column_b), (C, c, column_c), (D, d, column_d), (E, e, column_e));
+impl_bundle!(
+	(A, a, column_a),
+	(B, b, column_b),
+	(C, c, column_c),
+	(D, d, column_d),
+	(E, e, column_e),
+	(F, f, column_f)
+);
+impl_bundle!(
+	(A, a, column_a),
+	(B, b, column_b),
+	(C, c, column_c),
+	(D, d, column_d),
+	(E, e, column_e),
+	(F, f, column_f),
+	(G, g, column_g)
+);
+impl_bundle!(
+	(A, a, column_a),
+	(B, b, column_b),
+	(C, c, column_c),
+	(D, d, column_d),
+	(E, e, column_e),
+	(F, f, column_f),
+	(G, g, column_g),
+	(H, h, column_h)
+);
+
+/// The sorted component types of `B`, or an error if a type appears more than once.
+pub(crate) fn check_bundle<B: Bundle>() -> Result<Vec<(TypeId, &'static str)>> {
+	let mut type_ids = B::type_ids();
+	type_ids.sort_unstable();
+	if let Some(window) = type_ids.windows(2).find(|window| window[0].0 == window[1].0) {
+		return Err(Box::new(DuplicateComponentError { type_name: window[0].1 }));
+	}
+	Ok(type_ids)
+}
+
+impl World {
+	/// Create an entity holding every component of `bundle`.
+	pub fn spawn<B: Bundle>(&mut self, bundle: B) -> Result<Entity> {
+		Ok(self.spawn_batch(std::iter::once(bundle))?[0])
+	}
+
+	/// Create one entity per bundle. Allocator capacity is reserved up front and each component
+	/// column is looked up, borrowed and grown once for the whole batch.
+	///
+	/// `StableId` and `Name` components are indexed like components added one at a time. If any
+	/// bundle cannot be inserted or indexed, none of the entities are kept.
+	pub fn spawn_batch<B: Bundle, I: IntoIterator<Item = B>>(&mut self, bundles: I) -> Result<Vec<Entity>> {
+		let type_ids = check_bundle::<B>()?;
+		let bundles = bundles.into_iter().collect::<Vec<_>>();
+		B::register(self);
+		self.allocate_reserved_entities();
+		self.allocator.reserve(bundles.len());
+		let entities = self.create_entities(bundles.len());
+		if let Err(error) = B::insert_batch(&self.components, &entities, bundles).and_then(|()| self.index_batch(&entities, &type_ids)) {
+			self.remove_entities(&entities);
+			return Err(error);
+		}
+		#[cfg(feature = "tracing")]
+		for entity in entities.iter() {
+			for (_, type_name) in type_ids.iter() {
+				trace_event!(
+					entity.index = *entity.index(),
+					entity.generation = *entity.generation(),
+					component = *type_name,
+					"component added"
+				);
+			}
+		}
+		Ok(entities)
+	}
+
+	/// Index the `StableId` and `Name` components that `insert_batch` wrote to new entities.
+	fn index_batch(&mut self, entities: &[Entity], type_ids: &[(TypeId, &'static str)]) -> Result<()> {
+		let contains = |type_id: TypeId| type_ids.iter().any(|(id, _)| *id == type_id);
+		let (stable_ids, names) = (contains(TypeId::of::<StableId>()), contains(TypeId::of::<Name>()));
+		for entity in entities.iter().copied() {
+			if stable_ids {
+				let id: Option<Component> = self.get_component::<StableId>(entity).map(|id| Box::new(*id) as Component);
+				self.index_component(entity, TypeId::of::<StableId>(), id.as_ref())?;
+			}
+			if names {
+				let name: Option<Component> = self.get_component::<Name>(entity).map(|name| Box::new(name.clone()) as Component);
+				self.index_component(entity, TypeId::of::<Name>(), name.as_ref())?;
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Debug, Default, PartialEq, Copy, Clone)]
+	struct Position {
+		x: f32,
+		y: f32,
+	}
+
+	#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+	struct Health(u8);
+
+	#[test]
+	fn spawn_batch() -> Result<()> {
+		let mut world = World::new();
+		let existing = world.create_entity();
+		world.remove_entity(existing);
+
+		let entities = world.spawn_batch((0..10).map(|index| (Position { x: index as f32, y: 0.0 }, Health(index))))?;
+
+		assert_eq!(entities.len(), 10);
+		for (index, entity) in entities.iter().enumerate() {
+			assert_eq!(world.get_component::<Position>(*entity).as_deref(), Some(&Position { x: index as f32, y: 0.0 }));
+			assert_eq!(world.get_component::<Health>(*entity).as_deref(), Some(&Health(index as u8)));
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn spawn() -> Result<()> {
+		let mut world = World::new();
+		let entity = world.spawn((Health(3),))?;
+		assert_eq!(world.get_component::<Health>(entity).as_deref(), Some(&Health(3)));
+		Ok(())
+	}
+
+	#[test]
+	fn spawn_batch_indexes_components() -> Result<()> {
+		let mut world = World::new();
+		let entities = world.spawn_batch(vec![(Name::new("player"), StableId(7)), (Name::new("enemy"), StableId(8))])?;
+		assert_eq!(world.find_by_name("player"), Some(entities[0]));
+		assert_eq!(world.entity_by_stable_id(StableId(8)), Some(entities[1]));
+
+		assert!(
+			world
+				.spawn_batch(vec![(Name::new("camera"), StableId(9)), (Name::new("light"), StableId(7))])
+				.is_err()
+		);
+		assert_eq!(world.allocator.allocated_handles(), entities);
+		assert_eq!(world.find_by_name("camera"), None);
+		assert_eq!(world.entity_by_stable_id(StableId(9)), None);
+		assert_eq!(world.entity_by_stable_id(StableId(7)), Some(entities[0]));
+
+		Ok(())
+	}
+
+	#[test]
+	fn duplicate_components_in_bundle() {
+		let mut world = World::new();
+		assert!(world.spawn((Health(1), Position::default(), Health(2))).is_err());
+		assert!(world.allocator.allocated_handles().is_empty());
+	}
+}
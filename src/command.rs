@@ -0,0 +1,157 @@
+use crate::{
+	error::Result,
+	world::{Entity, World},
+};
+use std::cell::RefMut;
+
+pub type Command = Box<dyn FnOnce(&mut World) -> Result<()>>;
+
+/// Structural changes queued through a shared reference to a `World` and applied by
+/// `World::flush`.
+#[derive(Default)]
+pub struct CommandBuffer {
+	commands: Vec<Command>,
+}
+
+impl CommandBuffer {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn push(&mut self, command: impl FnOnce(&mut World) -> Result<()> + 'static) {
+		self.commands.push(Box::new(command));
+	}
+
+	pub fn add_component<T: 'static>(&mut self, entity: Entity, component: T) {
+		self.push(move |world| world.add_component(entity, component));
+	}
+
+	pub fn remove_component<T: 'static>(&mut self, entity: Entity) {
+		self.push(move |world| world.remove_component::<T>(entity));
+	}
+
+	pub fn remove_entity(&mut self, entity: Entity) {
+		self.push(move |world| {
+			world.remove_entity(entity);
+			Ok(())
+		});
+	}
+
+	/// Discard the commands queued after the first `len`.
+	pub fn truncate(&mut self, len: usize) {
+		self.commands.truncate(len);
+	}
+
+	pub fn len(&self) -> usize {
+		self.commands.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.commands.is_empty()
+	}
+}
+
+impl World {
+	/// Reserve an entity through a shared reference. The entity can be referenced by queued
+	/// commands right away and exists once the world is flushed.
+	pub fn reserve_entity(&self) -> Entity {
+		self.allocator.reserve_handle()
+	}
+
+	pub fn commands(&self) -> RefMut<'_, CommandBuffer> {
+		self.commands.borrow_mut()
+	}
+
+	/// Discard the commands queued after the first `queued`, and remove the entities reserved
+	/// after the first `reserved`, undoing what was queued since those counts were taken.
+	pub(crate) fn discard_commands_since(&mut self, queued: usize, reserved: usize) {
+		self.commands.get_mut().truncate(queued);
+		let mut entities = self.allocate_reserved_entities();
+		let discarded = entities.split_off(reserved.min(entities.len()));
+		self.remove_entities(&discarded);
+	}
+
+	/// Allocate every reserved entity, then apply queued commands in the order they were queued,
+	/// including commands queued while flushing.
+	///
+	/// Flushing stops at the first failing command and discards the commands after it.
+	pub fn flush(&mut self) -> Result<()> {
+		trace_span!("flush", commands = self.commands.get_mut().len());
+		self.allocate_reserved_entities();
+		loop {
+			let commands = std::mem::take(&mut self.commands.get_mut().commands);
+			if commands.is_empty() {
+				return Ok(());
+			}
+			for command in commands {
+				if let Err(error) = command(self) {
+					self.commands.get_mut().commands.clear();
+					return Err(error);
+				}
+			}
+			self.allocate_reserved_entities();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+	struct Health(u8);
+
+	#[test]
+	fn reserved_entities_are_usable_in_commands() -> Result<()> {
+		let mut world = World::new();
+		let removed = world.create_entity();
+		world.remove_entity(removed);
+
+		let entity = {
+			let world = &world;
+			let entity = world.reserve_entity();
+			world.commands().add_component(entity, Health(5));
+			entity
+		};
+		assert!(!world.entity_exists(entity));
+
+		world.flush()?;
+
+		assert!(world.entity_exists(entity));
+		assert_eq!(world.get_component::<Health>(entity).as_deref(), Some(&Health(5)));
+		assert!(!world.entity_exists(removed));
+		assert!(world.commands().is_empty());
+
+		Ok(())
+	}
+
+	#[test]
+	fn commands_apply_in_order() -> Result<()> {
+		let mut world = World::new();
+		let entity = world.create_entity();
+		world.commands().add_component(entity, Health(1));
+		world.commands().remove_component::<Health>(entity);
+		world.commands().push(move |world| {
+			world.commands().add_component(entity, Health(2));
+			Ok(())
+		});
+
+		world.flush()?;
+
+		assert_eq!(world.get_component::<Health>(entity).as_deref(), Some(&Health(2)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn flush_stops_at_failing_command() {
+		let mut world = World::new();
+		let entity = world.create_entity();
+		world.commands().push(|_| Err("failed".into()));
+		world.commands().add_component(entity, Health(1));
+
+		assert!(world.flush().is_err());
+		assert!(world.commands().is_empty());
+		assert!(world.get_component::<Health>(entity).is_none());
+	}
+}
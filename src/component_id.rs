@@ -0,0 +1,473 @@
+use self::error::{ComponentLayoutError, ComponentTypeMismatchError, DuplicateComponentNameError, UnknownComponentIdError};
+use crate::{
+	error::Result,
+	vec::{error::HandleNotFoundError, Slot},
+	world::{Component, ComponentVec, ComponentVecHandle, Entity, World},
+};
+use serde_json::Value;
+use std::{
+	any::{Any, TypeId},
+	cell::{Ref, RefCell, RefMut},
+	collections::HashMap,
+	ops::Deref,
+	rc::Rc,
+};
+
+pub mod error {
+	use super::ComponentId;
+
+	#[derive(Debug)]
+	pub struct UnknownComponentIdError {
+		pub id: ComponentId,
+	}
+
+	impl std::error::Error for UnknownComponentIdError {}
+
+	impl std::fmt::Display for UnknownComponentIdError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Component id {} is not registered.", self.id.0)
+		}
+	}
+
+	#[derive(Debug)]
+	pub struct DuplicateComponentNameError {
+		pub name: String,
+	}
+
+	impl std::error::Error for DuplicateComponentNameError {}
+
+	impl std::fmt::Display for DuplicateComponentNameError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "A component named '{}' is already registered.", self.name)
+		}
+	}
+
+	#[derive(Debug)]
+	pub struct ComponentLayoutError {
+		pub name: String,
+	}
+
+	impl std::error::Error for ComponentLayoutError {}
+
+	impl std::fmt::Display for ComponentLayoutError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Value does not match the layout of component '{}'.", self.name)
+		}
+	}
+
+	#[derive(Debug)]
+	pub struct ComponentTypeMismatchError {
+		pub name: String,
+	}
+
+	impl std::error::Error for ComponentTypeMismatchError {}
+
+	impl std::fmt::Display for ComponentTypeMismatchError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Value is not of the type of component '{}'.", self.name)
+		}
+	}
+}
+
+/// Identifies a kind of component, either a Rust type or a layout described at runtime by a
+/// script or plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ComponentId(pub u32);
+
+/// The shape of a component defined at runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentLayout {
+	/// An opaque blob of exactly `size` bytes.
+	Bytes { size: usize },
+
+	/// A JSON value shaped like `schema`. Objects must contain every key of the schema with a
+	/// value of the same shape, arrays must hold elements shaped like the first schema element
+	/// and `null` accepts any value.
+	Value { schema: Value },
+}
+
+impl ComponentLayout {
+	fn accepts(&self, component: &DynamicComponent) -> bool {
+		match (self, component) {
+			(Self::Bytes { size }, DynamicComponent::Bytes(bytes)) => bytes.len() == *size,
+			(Self::Value { schema }, DynamicComponent::Value(value)) => matches_schema(schema, value),
+			_ => false,
+		}
+	}
+}
+
+fn matches_schema(schema: &Value, value: &Value) -> bool {
+	match (schema, value) {
+		(Value::Null, _) => true,
+		(Value::Bool(_), Value::Bool(_)) | (Value::Number(_), Value::Number(_)) | (Value::String(_), Value::String(_)) => true,
+		(Value::Array(schema), Value::Array(values)) => schema.first().is_none_or(|schema| values.iter().all(|value| matches_schema(schema, value))),
+		(Value::Object(schema), Value::Object(object)) => schema.iter().all(|(key, schema)| object.get(key).is_some_and(|value| matches_schema(schema, value))),
+		_ => false,
+	}
+}
+
+/// The value of a component defined at runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DynamicComponent {
+	Bytes(Vec<u8>),
+	Value(Value),
+}
+
+/// A runtime component borrowed from its column. Writes go through `set`, which keeps the value
+/// in the component's layout.
+pub struct DynamicComponentMut<'a> {
+	value: RefMut<'a, DynamicComponent>,
+	name: &'a str,
+	layout: &'a ComponentLayout,
+}
+
+impl DynamicComponentMut<'_> {
+	pub fn set(&mut self, component: DynamicComponent) -> Result<()> {
+		if !self.layout.accepts(&component) {
+			return Err(Box::new(ComponentLayoutError { name: self.name.to_string() }));
+		}
+		*self.value = component;
+		Ok(())
+	}
+}
+
+impl Deref for DynamicComponentMut<'_> {
+	type Target = DynamicComponent;
+
+	fn deref(&self) -> &Self::Target {
+		&self.value
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentKind {
+	Type(TypeId),
+	Dynamic(ComponentLayout),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentInfo {
+	name: String,
+	kind: ComponentKind,
+}
+
+impl ComponentInfo {
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub const fn kind(&self) -> &ComponentKind {
+		&self.kind
+	}
+}
+
+/// Hands out a `ComponentId` per Rust component type and per runtime component layout.
+#[derive(Debug, Default, Clone)]
+pub struct ComponentIds {
+	components: Vec<ComponentInfo>,
+	types: HashMap<TypeId, ComponentId>,
+	names: HashMap<String, ComponentId>,
+}
+
+impl ComponentIds {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The id of the component type `T`, assigning one if it has none yet.
+	pub fn of<T: 'static>(&mut self) -> ComponentId {
+		if let Some(id) = self.types.get(&TypeId::of::<T>()) {
+			return *id;
+		}
+		let id = self.push(ComponentInfo {
+			name: std::any::type_name::<T>().to_string(),
+			kind: ComponentKind::Type(TypeId::of::<T>()),
+		});
+		self.types.insert(TypeId::of::<T>(), id);
+		id
+	}
+
+	/// Assign an id to a component defined at runtime.
+	pub fn register_dynamic(&mut self, name: impl Into<String>, layout: ComponentLayout) -> Result<ComponentId, DuplicateComponentNameError> {
+		let name = name.into();
+		if self.names.contains_key(&name) {
+			return Err(DuplicateComponentNameError { name });
+		}
+		Ok(self.push(ComponentInfo {
+			name,
+			kind: ComponentKind::Dynamic(layout),
+		}))
+	}
+
+	fn push(&mut self, info: ComponentInfo) -> ComponentId {
+		let id = ComponentId(u32::try_from(self.components.len()).expect("Component ids are exhausted"));
+		self.names.entry(info.name.clone()).or_insert(id);
+		self.components.push(info);
+		id
+	}
+
+	pub fn get<T: 'static>(&self) -> Option<ComponentId> {
+		self.types.get(&TypeId::of::<T>()).copied()
+	}
+
+	/// Look up a component by its runtime name, or by the type name of a Rust component.
+	pub fn by_name(&self, name: &str) -> Option<ComponentId> {
+		self.names.get(name).copied()
+	}
+
+	pub fn info(&self, id: ComponentId) -> Option<&ComponentInfo> {
+		self.components.get(id.0 as usize)
+	}
+}
+
+impl World {
+	/// The id of the component type `T`, registering the type if needed.
+	pub fn component_id<T: 'static>(&mut self) -> ComponentId {
+		self.register_component::<T>();
+		self.component_ids.of::<T>()
+	}
+
+	/// Define a component kind at runtime, for example from a script or a plugin.
+	pub fn register_dynamic_component(&mut self, name: impl Into<String>, layout: ComponentLayout) -> Result<ComponentId> {
+		let id = self.component_ids.register_dynamic(name, layout)?;
+		self.dynamic_components.insert(id, Rc::new(RefCell::new(ComponentVec::default())));
+		Ok(id)
+	}
+
+	pub const fn component_ids(&self) -> &ComponentIds {
+		&self.component_ids
+	}
+
+	/// Add a component by id. Components of Rust types must be boxed values of that type and
+	/// runtime components must be a `DynamicComponent` matching the component's layout.
+	pub fn add_component_by_id(&mut self, entity: Entity, id: ComponentId, component: Component) -> Result<()> {
+		let info = self.component_ids.info(id).cloned().ok_or(UnknownComponentIdError { id })?;
+		match info.kind() {
+			ComponentKind::Type(type_id) => {
+				if (*component).type_id() != *type_id {
+					return Err(Box::new(ComponentTypeMismatchError { name: info.name.clone() }));
+				}
+				self.assign_component_by_id(entity, *type_id, Some(component))
+			},
+			ComponentKind::Dynamic(layout) => {
+				if !component.downcast_ref::<DynamicComponent>().is_some_and(|component| layout.accepts(component)) {
+					return Err(Box::new(ComponentLayoutError { name: info.name.clone() }));
+				}
+				if !self.entity_exists(entity) {
+					return Err(Box::new(HandleNotFoundError { handle: entity }));
+				}
+				self.dynamic_components[&id].borrow_mut().insert(entity, component)?;
+				trace_event!(
+					entity.index = *entity.index(),
+					entity.generation = *entity.generation(),
+					component = info.name,
+					"component added"
+				);
+				Ok(())
+			},
+		}
+	}
+
+	pub fn add_dynamic_component(&mut self, entity: Entity, id: ComponentId, component: DynamicComponent) -> Result<()> {
+		self.add_component_by_id(entity, id, Box::new(component))
+	}
+
+	pub fn remove_component_by_id(&mut self, entity: Entity, id: ComponentId) -> Result<()> {
+		let info = self.component_ids.info(id).ok_or(UnknownComponentIdError { id })?;
+		match info.kind() {
+			ComponentKind::Type(type_id) => {
+				let type_id = *type_id;
+				self.assign_component_by_id(entity, type_id, None)
+			},
+			ComponentKind::Dynamic(_) => {
+				if !self.entity_exists(entity) {
+					return Err(Box::new(HandleNotFoundError { handle: entity }));
+				}
+				self.dynamic_components[&id].borrow_mut().remove(entity);
+				trace_event!(
+					entity.index = *entity.index(),
+					entity.generation = *entity.generation(),
+					component = info.name,
+					"component removed"
+				);
+				Ok(())
+			},
+		}
+	}
+
+	pub fn get_component_by_id(&self, entity: Entity, id: ComponentId) -> Option<Ref<'_, dyn Any>> {
+		let column = self.column_by_id(entity, id)?;
+		Some(Ref::map(column.borrow(), |column| column.get(entity).unwrap().as_ref()))
+	}
+
+	/// Borrow a component of a Rust type mutably. Runtime components are only writable through
+	/// `get_dynamic_component_mut`, which checks their layout.
+	pub fn get_component_by_id_mut(&self, entity: Entity, id: ComponentId) -> Option<RefMut<'_, dyn Any>> {
+		let ComponentKind::Type(type_id) = self.component_ids.info(id)?.kind() else {
+			return None;
+		};
+		let column = self.column_by_id(entity, id)?;
+		self.mark_component_borrowed(entity, *type_id);
+		Some(RefMut::map(column.borrow_mut(), |column| column.get_mut(entity).unwrap().as_mut()))
+	}
+
+	pub fn get_dynamic_component(&self, entity: Entity, id: ComponentId) -> Option<Ref<'_, DynamicComponent>> {
+		Ref::filter_map(self.get_component_by_id(entity, id)?, |component| component.downcast_ref::<DynamicComponent>()).ok()
+	}
+
+	pub fn get_dynamic_component_mut(&self, entity: Entity, id: ComponentId) -> Option<DynamicComponentMut<'_>> {
+		let info = self.component_ids.info(id)?;
+		let ComponentKind::Dynamic(layout) = info.kind() else {
+			return None;
+		};
+		let column = self.column_by_id(entity, id)?;
+		let value = RefMut::filter_map(column.borrow_mut(), |column| column.get_mut(entity)?.downcast_mut::<DynamicComponent>()).ok()?;
+		Some(DynamicComponentMut {
+			value,
+			name: info.name(),
+			layout,
+		})
+	}
+
+	/// Copy the columns of every runtime component, for forking a world.
+	pub(crate) fn clone_dynamic_components(&self) -> HashMap<ComponentId, ComponentVecHandle> {
+		self.dynamic_components
+			.iter()
+			.map(|(id, column)| {
+				let elements = column
+					.borrow()
+					.iter()
+					.map(|slot| {
+						slot.as_ref().and_then(|slot| {
+							slot.downcast_ref::<DynamicComponent>()
+								.map(|component| Slot::new(Box::new(component.clone()) as Component, *slot.generation()))
+						})
+					})
+					.collect();
+				(*id, Rc::new(RefCell::new(ComponentVec::new(elements))))
+			})
+			.collect()
+	}
+
+	/// Copy the runtime components of `entity` with the names and layouts they are registered
+	/// under.
+	pub(crate) fn clone_dynamic_components_of(&self, entity: Entity) -> Vec<(String, ComponentLayout, DynamicComponent)> {
+		self.dynamic_components
+			.iter()
+			.filter_map(|(id, column)| {
+				let component = column.borrow().get(entity)?.downcast_ref::<DynamicComponent>()?.clone();
+				let info = self.component_ids.info(*id)?;
+				let ComponentKind::Dynamic(layout) = info.kind() else {
+					return None;
+				};
+				Some((info.name.clone(), layout.clone(), component))
+			})
+			.collect()
+	}
+
+	/// Every enabled entity holding all of the components `ids`, in index order.
+	pub fn query_by_ids(&self, ids: &[ComponentId]) -> Vec<Entity> {
+		self.enabled_entities()
+			.into_iter()
+			.filter(|entity| ids.iter().all(|id| self.column_by_id(*entity, *id).is_some()))
+			.collect()
+	}
+
+	/// The column of the component `id`, if `entity` exists and holds that component.
+	fn column_by_id(&self, entity: Entity, id: ComponentId) -> Option<&ComponentVecHandle> {
+		if !self.entity_exists(entity) {
+			return None;
+		}
+		let column = match self.component_ids.info(id)?.kind() {
+			ComponentKind::Type(type_id) => self.components.get(type_id)?,
+			ComponentKind::Dynamic(_) => self.dynamic_components.get(&id)?,
+		};
+		column.borrow().get(entity).is_some().then_some(column)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[derive(Debug, Default, PartialEq, Copy, Clone)]
+	struct Position {
+		x: f32,
+		y: f32,
+	}
+
+	#[test]
+	fn rust_components_by_id() -> Result<()> {
+		let mut world = World::new();
+		let position = world.component_id::<Position>();
+		assert_eq!(world.component_id::<Position>(), position);
+		assert_eq!(world.component_ids().by_name(std::any::type_name::<Position>()), Some(position));
+
+		let entity = world.create_entity();
+		assert!(world.add_component_by_id(entity, position, Box::new(3_u8)).is_err());
+		world.add_component_by_id(entity, position, Box::new(Position { x: 1.0, y: 2.0 }))?;
+		assert_eq!(world.get_component::<Position>(entity).as_deref(), Some(&Position { x: 1.0, y: 2.0 }));
+		assert_eq!(
+			world.get_component_by_id(entity, position).unwrap().downcast_ref::<Position>(),
+			Some(&Position { x: 1.0, y: 2.0 })
+		);
+
+		world.remove_component_by_id(entity, position)?;
+		assert!(world.get_component::<Position>(entity).is_none());
+
+		Ok(())
+	}
+
+	#[test]
+	fn dynamic_components() -> Result<()> {
+		let mut world = World::new();
+		let position = world.component_id::<Position>();
+		let health = world.register_dynamic_component("Health", ComponentLayout::Value { schema: json!({ "value": 0 }) })?;
+		let tag = world.register_dynamic_component("Tag", ComponentLayout::Bytes { size: 4 })?;
+		assert!(world.register_dynamic_component("Tag", ComponentLayout::Bytes { size: 2 }).is_err());
+		assert_eq!(world.component_ids().by_name("Health"), Some(health));
+
+		let entities = world.create_entities(3);
+		assert!(world.add_dynamic_component(entities[0], health, DynamicComponent::Value(json!({ "hp": 1 }))).is_err());
+		assert!(world.add_dynamic_component(entities[0], tag, DynamicComponent::Bytes(vec![0; 3])).is_err());
+
+		for entity in entities.iter() {
+			world.add_dynamic_component(*entity, health, DynamicComponent::Value(json!({ "value": 10, "regen": true })))?;
+		}
+		world.add_dynamic_component(entities[1], tag, DynamicComponent::Bytes(vec![1, 2, 3, 4]))?;
+		world.add_component(entities[1], Position::default())?;
+		world.add_component(entities[2], Position::default())?;
+
+		world
+			.get_dynamic_component_mut(entities[1], health)
+			.unwrap()
+			.set(DynamicComponent::Value(json!({ "value": 5 })))?;
+		assert!(
+			world
+				.get_dynamic_component_mut(entities[1], tag)
+				.unwrap()
+				.set(DynamicComponent::Bytes(vec![1]))
+				.is_err()
+		);
+		assert!(world.get_component_by_id_mut(entities[1], tag).is_none());
+		assert_eq!(
+			world.get_dynamic_component(entities[1], health).as_deref(),
+			Some(&DynamicComponent::Value(json!({ "value": 5 })))
+		);
+
+		assert_eq!(world.query_by_ids(&[health, position]), vec![entities[1], entities[2]]);
+		assert_eq!(world.query_by_ids(&[tag, position]), vec![entities[1]]);
+
+		world.remove_component_by_id(entities[1], tag)?;
+		world.remove_entity(entities[2]);
+		assert!(world.remove_component_by_id(entities[2], health).is_err());
+		assert!(world.query_by_ids(&[tag]).is_empty());
+		assert_eq!(world.query_by_ids(&[health]), vec![entities[0], entities[1]]);
+
+		world.disable(entities[0])?;
+		assert_eq!(world.query_by_ids(&[health]), vec![entities[1]]);
+
+		Ok(())
+	}
+}
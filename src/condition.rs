@@ -0,0 +1,250 @@
+use crate::{
+	error::Result,
+	schedule::{ErrorPolicy, OnError, System},
+	time::Time,
+	world::World,
+};
+use std::time::Duration;
+
+/// A predicate deciding whether a system runs, evaluated against the `World` right before the
+/// system would run.
+///
+/// Implemented for every `FnMut(&World) -> bool`. Conditions are combined with `and`, `or` and
+/// `not`.
+pub trait Condition {
+	fn evaluate(&mut self, world: &World) -> bool;
+}
+
+impl<F: FnMut(&World) -> bool> Condition for F {
+	fn evaluate(&mut self, world: &World) -> bool {
+		self(world)
+	}
+}
+
+pub trait ConditionExt: Condition + Sized {
+	/// True if both conditions are. `other` is not evaluated if `self` is false.
+	fn and<C: Condition>(self, other: C) -> And<Self, C> {
+		And(self, other)
+	}
+
+	/// True if either condition is. `other` is not evaluated if `self` is true.
+	fn or<C: Condition>(self, other: C) -> Or<Self, C> {
+		Or(self, other)
+	}
+}
+
+impl<C: Condition> ConditionExt for C {}
+
+pub struct And<A, B>(A, B);
+
+impl<A: Condition, B: Condition> Condition for And<A, B> {
+	fn evaluate(&mut self, world: &World) -> bool {
+		self.0.evaluate(world) && self.1.evaluate(world)
+	}
+}
+
+pub struct Or<A, B>(A, B);
+
+impl<A: Condition, B: Condition> Condition for Or<A, B> {
+	fn evaluate(&mut self, world: &World) -> bool {
+		self.0.evaluate(world) || self.1.evaluate(world)
+	}
+}
+
+pub struct Not<C>(C);
+
+impl<C: Condition> Condition for Not<C> {
+	fn evaluate(&mut self, world: &World) -> bool {
+		!self.0.evaluate(world)
+	}
+}
+
+pub const fn not<C: Condition>(condition: C) -> Not<C> {
+	Not(condition)
+}
+
+/// True while the resource `T` exists.
+pub fn resource_exists<T: 'static>(world: &World) -> bool {
+	world.resources().borrow().get::<T>().is_some()
+}
+
+/// True when the resource `T` was inserted or borrowed mutably since the condition was last
+/// evaluated.
+pub fn resource_changed<T: 'static>() -> impl Condition {
+	let mut last_seen = None;
+	move |world: &World| {
+		let change_tick = world.resources().borrow().change_tick::<T>();
+		let changed = change_tick.is_some() && change_tick != last_seen;
+		last_seen = change_tick;
+		changed
+	}
+}
+
+/// True once every time `duration` of `Time::elapsed` has passed.
+pub fn on_timer(duration: Duration) -> impl Condition {
+	let mut next = duration;
+	move |world: &World| {
+		let Some(elapsed) = world.resources().borrow().get::<Time>().map(Time::elapsed) else {
+			return false;
+		};
+		if elapsed < next {
+			return false;
+		}
+		// Advance by whole periods, so the timer does not drift with the frame it fired on, and
+		// skip the periods a long frame missed instead of firing for each of them.
+		while next <= elapsed && !duration.is_zero() {
+			next += duration;
+		}
+		true
+	}
+}
+
+/// True on every `n`th evaluation. Panics if `n` is zero.
+pub fn every_n_ticks(n: u64) -> impl Condition {
+	assert!(n > 0, "every_n_ticks needs a period of at least one tick");
+	let mut ticks = 0;
+	move |_: &World| {
+		ticks += 1;
+		ticks % n == 0
+	}
+}
+
+/// A system that only runs while its condition holds, see `SystemExt::run_if`.
+pub struct RunIf<S, C> {
+	system: S,
+	condition: C,
+}
+
+impl<S: System, C: Condition> System for RunIf<S, C> {
+	fn name(&self) -> &str {
+		self.system.name()
+	}
+
+	fn error_policy(&self) -> Option<ErrorPolicy> {
+		self.system.error_policy()
+	}
+
+	fn run(&mut self, world: &World) -> Result<()> {
+		if !self.condition.evaluate(world) {
+			world.skip_system_run();
+			return Ok(());
+		}
+		self.system.run(world)
+	}
+}
+
+pub trait SystemExt: System + Sized {
+	/// Skip the system whenever `condition` is false.
+	fn run_if<C: Condition>(self, condition: C) -> RunIf<Self, C> {
+		RunIf { system: self, condition }
+	}
+
+	/// Handle failures of this system with `policy` instead of the schedule's policy.
+	fn on_error(self, policy: ErrorPolicy) -> OnError<Self> {
+		OnError { system: self, policy }
+	}
+}
+
+impl<S: System> SystemExt for S {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schedule::Schedule;
+
+	#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+	struct Ticks(u32);
+
+	struct Paused;
+
+	struct Score(u32);
+
+	fn tick(world: &World) -> Result<()> {
+		world.resources().borrow_mut().get_mut::<Ticks>().unwrap().0 += 1;
+		Ok(())
+	}
+
+	fn ticks(world: &World) -> u32 {
+		world.resources().borrow().get::<Ticks>().unwrap().0
+	}
+
+	fn world() -> World {
+		let world = World::new();
+		world.resources().borrow_mut().insert(Ticks::default());
+		world
+	}
+
+	#[test]
+	fn combined_conditions() -> Result<()> {
+		let mut world = world();
+		let mut schedule = Schedule::new();
+		schedule.add_system(tick.run_if(not(resource_exists::<Paused>).and(every_n_ticks(2).or(resource_exists::<Score>))));
+
+		schedule.run(&mut world)?;
+		schedule.run(&mut world)?;
+		assert_eq!(ticks(&world), 1);
+
+		world.resources().borrow_mut().insert(Score(0));
+		schedule.run(&mut world)?;
+		assert_eq!(ticks(&world), 2);
+
+		world.resources().borrow_mut().insert(Paused);
+		schedule.run(&mut world)?;
+		assert_eq!(ticks(&world), 2);
+
+		Ok(())
+	}
+
+	#[test]
+	fn resource_changes() -> Result<()> {
+		let mut world = world();
+		let mut schedule = Schedule::new();
+		schedule.add_system(tick.run_if(resource_changed::<Score>()));
+
+		schedule.run(&mut world)?;
+		world.resources().borrow_mut().insert(Score(0));
+		schedule.run(&mut world)?;
+		schedule.run(&mut world)?;
+		assert_eq!(ticks(&world), 1);
+
+		world.resources().borrow_mut().get_mut::<Score>().unwrap().0 += 1;
+		schedule.run(&mut world)?;
+		assert_eq!(ticks(&world), 2);
+
+		Ok(())
+	}
+
+	#[test]
+	fn timers() -> Result<()> {
+		let mut world = world();
+		world.resources().borrow_mut().insert(Time::manual(Duration::from_millis(400)));
+		let mut schedule = Schedule::new();
+		schedule.add_system(tick.run_if(on_timer(Duration::from_secs(1))));
+
+		for _ in 0..5 {
+			world.resources().borrow_mut().get_mut::<Time>().unwrap().update();
+			schedule.run(&mut world)?;
+		}
+		assert_eq!(ticks(&world), 2);
+
+		Ok(())
+	}
+
+	#[test]
+	fn timers_keep_their_period_with_uneven_frames() -> Result<()> {
+		let mut world = world();
+		world.resources().borrow_mut().insert(Time::manual(Duration::ZERO));
+		let mut schedule = Schedule::new();
+		schedule.add_system(tick.run_if(on_timer(Duration::from_secs(1))));
+
+		let mut fired = Vec::new();
+		for delta in [700, 700, 700, 700, 300, 2500, 500] {
+			world.resources().borrow_mut().get_mut::<Time>().unwrap().advance(Duration::from_millis(delta));
+			schedule.run(&mut world)?;
+			fired.push(ticks(&world));
+		}
+		assert_eq!(fired, vec![0, 1, 2, 2, 3, 4, 5]);
+
+		Ok(())
+	}
+}
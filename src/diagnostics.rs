@@ -0,0 +1,349 @@
+use crate::{error::Result, schedule::SystemId};
+use serde::Serialize;
+use std::{
+	collections::{BTreeMap, VecDeque},
+	time::Duration,
+};
+
+/// The most recent durations of something that runs repeatedly, such as a system or a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timings {
+	samples: VecDeque<Duration>,
+	capacity: usize,
+}
+
+impl Timings {
+	/// Panics if `capacity` is zero.
+	pub fn new(capacity: usize) -> Self {
+		assert!(capacity > 0, "Timings need room for at least one sample");
+		Self {
+			samples: VecDeque::with_capacity(capacity),
+			capacity,
+		}
+	}
+
+	/// Add a sample, dropping the oldest one once the window is full.
+	pub fn record(&mut self, duration: Duration) {
+		if self.samples.len() == self.capacity {
+			self.samples.pop_front();
+		}
+		self.samples.push_back(duration);
+	}
+
+	pub fn len(&self) -> usize {
+		self.samples.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.samples.is_empty()
+	}
+
+	pub fn last(&self) -> Option<Duration> {
+		self.samples.back().copied()
+	}
+
+	pub fn max(&self) -> Duration {
+		self.samples.iter().max().copied().unwrap_or_default()
+	}
+
+	pub fn average(&self) -> Duration {
+		match u32::try_from(self.samples.len()) {
+			Ok(0) | Err(_) => Duration::ZERO,
+			Ok(count) => self.samples.iter().sum::<Duration>() / count,
+		}
+	}
+
+	/// The nearest-rank percentile of the samples, with `percentile` between 0 and 100.
+	pub fn percentile(&self, percentile: f64) -> Duration {
+		if self.samples.is_empty() {
+			return Duration::ZERO;
+		}
+		let mut samples = self.samples.iter().copied().collect::<Vec<_>>();
+		samples.sort_unstable();
+		let rank = (percentile.clamp(0.0, 100.0) / 100.0 * samples.len() as f64).ceil() as usize;
+		samples[rank.clamp(1, samples.len()) - 1]
+	}
+
+	pub fn report(&self) -> TimingReport {
+		let milliseconds = |duration: Duration| duration.as_secs_f64() * 1000.0;
+		TimingReport {
+			samples: self.len(),
+			average_ms: milliseconds(self.average()),
+			p50_ms: milliseconds(self.percentile(50.0)),
+			p95_ms: milliseconds(self.percentile(95.0)),
+			p99_ms: milliseconds(self.percentile(99.0)),
+			max_ms: milliseconds(self.max()),
+		}
+	}
+}
+
+/// What `Diagnostics` records about one system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemDiagnostics {
+	name: String,
+	invocations: u64,
+	entities: usize,
+	total_entities: u64,
+	timings: Timings,
+}
+
+impl SystemDiagnostics {
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub const fn invocations(&self) -> u64 {
+		self.invocations
+	}
+
+	/// The number of entities processed by the last run. Only systems generated by `system!`
+	/// report the entities they process.
+	pub const fn entities(&self) -> usize {
+		self.entities
+	}
+
+	/// The number of entities processed by all runs so far.
+	pub const fn total_entities(&self) -> u64 {
+		self.total_entities
+	}
+
+	pub const fn timings(&self) -> &Timings {
+		&self.timings
+	}
+}
+
+/// Per-system and per-frame timings, recorded while this resource exists.
+///
+/// Schedules record the wall time and the number of processed entities of every system run, and
+/// `App::update` records the duration of every frame. Averages and percentiles cover the last
+/// `window` samples. Systems are recorded by `SystemId`, so systems sharing a name are kept apart,
+/// and runs skipped by `run_if` are not recorded. Recording does not count as a change for
+/// `resource_changed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+	window: usize,
+	frames: Timings,
+	systems: BTreeMap<SystemId, SystemDiagnostics>,
+}
+
+impl Default for Diagnostics {
+	fn default() -> Self {
+		Self::with_window(120)
+	}
+}
+
+impl Diagnostics {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Panics if `window` is zero.
+	pub fn with_window(window: usize) -> Self {
+		Self {
+			window,
+			frames: Timings::new(window),
+			systems: BTreeMap::new(),
+		}
+	}
+
+	pub const fn window(&self) -> usize {
+		self.window
+	}
+
+	pub fn record_system(&mut self, id: SystemId, name: &str, duration: Duration, entities: usize) {
+		let window = self.window;
+		let system = self.systems.entry(id).or_insert_with(|| SystemDiagnostics {
+			name: name.to_string(),
+			invocations: 0,
+			entities: 0,
+			total_entities: 0,
+			timings: Timings::new(window),
+		});
+		system.invocations += 1;
+		system.entities = entities;
+		system.total_entities += entities as u64;
+		system.timings.record(duration);
+	}
+
+	pub fn record_frame(&mut self, duration: Duration) {
+		self.frames.record(duration);
+	}
+
+	pub const fn frames(&self) -> &Timings {
+		&self.frames
+	}
+
+	pub fn system(&self, id: SystemId) -> Option<&SystemDiagnostics> {
+		self.systems.get(&id)
+	}
+
+	/// The first system recorded under `name`.
+	pub fn system_by_name(&self, name: &str) -> Option<&SystemDiagnostics> {
+		self.systems.values().find(|system| system.name == name)
+	}
+
+	/// Every recorded system, in the order the systems were created.
+	pub fn systems(&self) -> impl Iterator<Item = (SystemId, &SystemDiagnostics)> {
+		self.systems.iter().map(|(id, system)| (*id, system))
+	}
+
+	/// Forget everything recorded so far.
+	pub fn clear(&mut self) {
+		self.frames = Timings::new(self.window);
+		self.systems.clear();
+	}
+
+	/// A summary of the frames and of every system, the most expensive systems first.
+	pub fn report(&self) -> DiagnosticsReport {
+		let mut systems = self
+			.systems
+			.iter()
+			.map(|(id, system)| SystemReport {
+				id: id.0,
+				name: system.name.clone(),
+				invocations: system.invocations,
+				entities: system.entities,
+				timings: system.timings.report(),
+			})
+			.collect::<Vec<_>>();
+		systems.sort_by(|a, b| b.timings.average_ms.total_cmp(&a.timings.average_ms));
+		DiagnosticsReport {
+			frame: self.frames.report(),
+			systems,
+		}
+	}
+
+	pub fn to_json(&self) -> Result<String> {
+		trace_span!("diagnostics_to_json");
+		Ok(serde_json::to_string_pretty(&self.report())?)
+	}
+
+	/// The report as a plain-text table with one row per system and a final row for frames.
+	pub fn to_table(&self) -> String {
+		let report = self.report();
+		let width = report.systems.iter().map(|system| system.name.len()).max().unwrap_or_default().max("system".len());
+		let mut table = format!(
+			"{:<width$} {:>8} {:>8} {:>9} {:>9} {:>9} {:>9} {:>9}\n",
+			"system", "calls", "entities", "avg ms", "p50 ms", "p95 ms", "p99 ms", "max ms"
+		);
+		let mut row = |name: &str, calls: String, entities: String, timings: &TimingReport| {
+			table += &format!(
+				"{:<width$} {:>8} {:>8} {:>9.3} {:>9.3} {:>9.3} {:>9.3} {:>9.3}\n",
+				name, calls, entities, timings.average_ms, timings.p50_ms, timings.p95_ms, timings.p99_ms, timings.max_ms
+			);
+		};
+		for system in report.systems.iter() {
+			row(&system.name, system.invocations.to_string(), system.entities.to_string(), &system.timings);
+		}
+		row("frame", report.frame.samples.to_string(), String::new(), &report.frame);
+		table
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimingReport {
+	pub samples: usize,
+	pub average_ms: f64,
+	pub p50_ms: f64,
+	pub p95_ms: f64,
+	pub p99_ms: f64,
+	pub max_ms: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SystemReport {
+	pub id: u64,
+	pub name: String,
+	pub invocations: u64,
+	pub entities: usize,
+	#[serde(flatten)]
+	pub timings: TimingReport,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiagnosticsReport {
+	pub frame: TimingReport,
+	pub systems: Vec<SystemReport>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		condition::SystemExt,
+		local::{with_local, Local},
+		schedule::Schedule,
+		system,
+		world::{Entity, World},
+	};
+
+	#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+	struct Health(i32);
+
+	system!(heal_system, [_resources, _entity], (), (health: Health) -> Result<()> {
+		health.0 += 1;
+		Ok(())
+	});
+
+	#[test]
+	fn systems_sharing_a_name_are_kept_apart() -> Result<()> {
+		let mut world = World::new();
+		world.resources().borrow_mut().insert(Diagnostics::new());
+		let counter = |_: &mut Local<u32>, _: &World| Ok(());
+		let mut schedule = Schedule::new();
+		let ids = [
+			schedule.add_system(with_local(counter)),
+			schedule.add_system(with_local(counter)),
+			schedule.add_system(with_local(counter).run_if(|_: &World| false)),
+		];
+		schedule.run(&mut world)?;
+
+		let resources = world.resources().borrow();
+		let diagnostics = resources.get::<Diagnostics>().unwrap();
+		assert_eq!(diagnostics.systems().count(), 2);
+		assert_eq!(diagnostics.system(ids[0]).unwrap().invocations(), 1);
+		assert_eq!(diagnostics.system(ids[1]).unwrap().invocations(), 1);
+		assert!(diagnostics.system(ids[2]).is_none());
+
+		Ok(())
+	}
+
+	#[test]
+	fn percentiles() {
+		let mut timings = Timings::new(4);
+		(1..=5).for_each(|milliseconds| timings.record(Duration::from_millis(milliseconds)));
+		assert_eq!(timings.len(), 4);
+		assert_eq!(timings.average(), Duration::from_micros(3500));
+		assert_eq!(timings.percentile(50.0), Duration::from_millis(3));
+		assert_eq!(timings.percentile(99.0), Duration::from_millis(5));
+		assert_eq!(timings.max(), Duration::from_millis(5));
+	}
+
+	#[test]
+	fn schedules_record_systems() -> Result<()> {
+		let mut world = World::new();
+		world.resources().borrow_mut().insert(Diagnostics::new());
+		let entities: Vec<Entity> = world.create_entities(3);
+		for entity in entities.iter().take(2) {
+			world.add_component(*entity, Health(0))?;
+		}
+
+		let mut schedule = Schedule::new();
+		let heal = schedule.add_system(heal_system);
+		schedule.run(&mut world)?;
+		schedule.run(&mut world)?;
+
+		let resources = world.resources().borrow();
+		let diagnostics = resources.get::<Diagnostics>().unwrap();
+		let (id, system) = diagnostics.systems().next().unwrap();
+		assert_eq!(id, heal);
+		assert!(system.name().ends_with("heal_system"));
+		assert_eq!((system.invocations(), system.entities(), system.total_entities()), (2, 2, 4));
+
+		let json = serde_json::from_str::<serde_json::Value>(&diagnostics.to_json()?)?;
+		assert_eq!(json["systems"][0]["invocations"], 2);
+		assert_eq!(diagnostics.to_table().lines().count(), 3);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,178 @@
+use crate::world::World;
+use std::hash::{Hash, Hasher};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A 64-bit FNV-1a hasher whose output does not depend on the platform, the process or the Rust
+/// release. Integers are always fed in little-endian order and `usize` is widened to 64 bits.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher {
+	state: u64,
+}
+
+impl StableHasher {
+	pub const fn new() -> Self {
+		Self { state: FNV_OFFSET_BASIS }
+	}
+}
+
+impl Default for StableHasher {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Hasher for StableHasher {
+	fn finish(&self) -> u64 {
+		self.state
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		for byte in bytes {
+			self.state ^= u64::from(*byte);
+			self.state = self.state.wrapping_mul(FNV_PRIME);
+		}
+	}
+
+	fn write_u16(&mut self, value: u16) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_u32(&mut self, value: u32) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_u64(&mut self, value: u64) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_u128(&mut self, value: u128) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_usize(&mut self, value: usize) {
+		self.write_u64(value as u64);
+	}
+
+	fn write_i16(&mut self, value: i16) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_i32(&mut self, value: i32) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_i64(&mut self, value: i64) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_i128(&mut self, value: i128) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_isize(&mut self, value: isize) {
+		self.write_i64(value as i64);
+	}
+}
+
+impl World {
+	/// A deterministic digest of the allocator and every component column registered as hashable.
+	///
+	/// Columns are visited in order of the name they were registered as hashable with, and entries
+	/// in order of entity index, so two worlds with the same state produce the same digest
+	/// regardless of `HashMap` iteration order or the compiler that built them. Runtime components,
+	/// which are registered only by `ComponentId`, are not part of the digest.
+	pub fn state_hash(&self) -> u64 {
+		let mut hasher = StableHasher::new();
+		self.allocator.hash(&mut hasher);
+
+		let mut columns = self
+			.components
+			.iter()
+			.filter_map(|(type_id, column)| {
+				self.registry
+					.component(type_id)
+					.and_then(|registration| registration.hash_name())
+					.map(|name| (name, type_id, column))
+			})
+			.collect::<Vec<_>>();
+		columns.sort_by_key(|(name, ..)| *name);
+
+		for (name, type_id, column) in columns {
+			name.hash(&mut hasher);
+			column.borrow().iter().enumerate().for_each(|(index, slot)| {
+				if let Some(slot) = slot {
+					(index, *slot.generation()).hash(&mut hasher);
+					self.registry.hash_component(type_id, slot, &mut hasher);
+				}
+			});
+		}
+
+		hasher.finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::error::Result;
+
+	#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+	struct Position {
+		x: i32,
+		y: i32,
+	}
+
+	#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+	struct Health(u8);
+
+	#[derive(Debug, Default, PartialEq, Copy, Clone)]
+	struct Velocity(f32);
+
+	#[test]
+	fn stable_hasher_is_fnv1a() {
+		let mut hasher = StableHasher::new();
+		hasher.write(b"a");
+		assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+	}
+
+	#[test]
+	fn state_hash_ignores_registration_order() -> Result<()> {
+		let mut first = World::new();
+		first.register_hashable_component::<Position>("Position");
+		first.register_hashable_component::<Health>("Health");
+
+		let mut second = World::new();
+		second.register_hashable_component::<Health>("Health");
+		second.register_hashable_component::<Position>("Position");
+
+		for world in [&mut first, &mut second] {
+			let entity = world.create_entity();
+			world.add_component(entity, Position { x: 1, y: 2 })?;
+			world.add_component(entity, Health(10))?;
+		}
+
+		assert_eq!(first.state_hash(), second.state_hash());
+
+		Ok(())
+	}
+
+	#[test]
+	fn state_hash_tracks_hashable_components() -> Result<()> {
+		let mut world = World::new();
+		world.register_hashable_component::<Position>("Position");
+		let entity = world.create_entity();
+		world.add_component(entity, Position::default())?;
+		world.add_component(entity, Velocity(1.0))?;
+		let hash = world.state_hash();
+
+		world.get_component_mut::<Velocity>(entity).unwrap().0 = 2.0;
+		assert_eq!(world.state_hash(), hash);
+
+		world.get_component_mut::<Position>(entity).unwrap().x = 5;
+		assert_ne!(world.state_hash(), hash);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,10 @@
+use crate::world::Entity;
+use serde::{Deserialize, Serialize};
+
+/// The entity this entity was spawned under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Parent(pub Entity);
+
+/// The entities spawned under this entity.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Children(pub Vec<Entity>);
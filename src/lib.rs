@@ -1,7 +1,50 @@
 #![forbid(unsafe_code)]
 #![forbid(clippy::all, clippy::nursery, clippy::cargo)]
 
+// Lets `#[derive(Reflect)]` refer to `::parsecs` inside this crate as well.
+extern crate self as parsecs;
+
+/// Enter a `tracing` span until the end of the enclosing block. Expands to nothing unless the
+/// `tracing` feature is enabled.
+macro_rules! trace_span {
+	($($arg:tt)*) => {
+		#[cfg(feature = "tracing")]
+		let _span = tracing::info_span!($($arg)*).entered();
+	};
+}
+
+/// Emit a `tracing` event at the trace level. Expands to nothing unless the `tracing` feature is
+/// enabled.
+macro_rules! trace_event {
+	($($arg:tt)*) => {
+		#[cfg(feature = "tracing")]
+		tracing::trace!($($arg)*);
+	};
+}
+
+pub mod app;
+pub mod bundle;
+pub mod command;
+pub mod component_id;
+pub mod condition;
+pub mod diagnostics;
+pub mod hash;
+pub mod hierarchy;
+pub mod local;
+pub mod name;
+pub mod pool;
+pub mod prefab;
+pub mod reflect;
+pub mod registry;
 pub mod resource;
+pub mod scene;
+pub mod schedule;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod snapshot;
+pub mod stable_id;
+pub mod state;
+pub mod time;
 pub mod vec;
 pub mod world;
 
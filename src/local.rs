@@ -0,0 +1,117 @@
+use crate::{error::Result, schedule::System, world::World};
+use std::ops::{Deref, DerefMut};
+
+/// Creates a value from the `World`, for state that depends on resources or entities.
+///
+/// Implemented for every `Default` type, which ignores the world.
+pub trait FromWorld {
+	fn from_world(world: &World) -> Self;
+}
+
+impl<T: Default> FromWorld for T {
+	fn from_world(_: &World) -> Self {
+		Self::default()
+	}
+}
+
+/// State owned by a single system instance that persists across its runs, see `with_local`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Local<T>(pub T);
+
+impl<T> Deref for Local<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.0
+	}
+}
+
+impl<T> DerefMut for Local<T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.0
+	}
+}
+
+/// A system with `Local` state, created by `with_local`.
+pub struct LocalSystem<T, F> {
+	local: Option<Local<T>>,
+	system: F,
+}
+
+impl<T: FromWorld, F: FnMut(&mut Local<T>, &World) -> Result<()>> System for LocalSystem<T, F> {
+	fn name(&self) -> &str {
+		std::any::type_name::<F>()
+	}
+
+	fn run(&mut self, world: &World) -> Result<()> {
+		let local = self.local.get_or_insert_with(|| Local(T::from_world(world)));
+		(self.system)(local, world)
+	}
+}
+
+/// Turn a function taking `Local<T>` state and the world into a system.
+///
+/// The state is created with `FromWorld` on the first run and kept by this system instance, so two
+/// systems made from the same function do not share it. Use a tuple for several values.
+pub const fn with_local<T: FromWorld, F: FnMut(&mut Local<T>, &World) -> Result<()>>(system: F) -> LocalSystem<T, F> {
+	LocalSystem { local: None, system }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schedule::Schedule;
+
+	#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+	struct Total(u32);
+
+	struct Step {
+		amount: u32,
+	}
+
+	impl FromWorld for Step {
+		fn from_world(world: &World) -> Self {
+			Self {
+				amount: world.resources().borrow().get::<Total>().unwrap().0,
+			}
+		}
+	}
+
+	fn count(counter: &mut Local<u32>, world: &World) -> Result<()> {
+		**counter += 1;
+		world.resources().borrow_mut().get_mut::<Total>().unwrap().0 += **counter;
+		Ok(())
+	}
+
+	#[test]
+	fn locals_persist_per_system() -> Result<()> {
+		let mut world = World::new();
+		world.resources().borrow_mut().insert(Total::default());
+		let mut schedule = Schedule::new();
+		schedule.add_system(with_local(count));
+		schedule.add_system(with_local(count));
+
+		schedule.run(&mut world)?;
+		schedule.run(&mut world)?;
+		assert_eq!(world.resources().borrow().get::<Total>(), Some(&Total(6)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn locals_from_world() -> Result<()> {
+		let mut world = World::new();
+		world.resources().borrow_mut().insert(Total(10));
+		let mut schedule = Schedule::new();
+		schedule.add_system(with_local(|step: &mut Local<Step>, world: &World| {
+			world.resources().borrow_mut().get_mut::<Total>().unwrap().0 += step.amount;
+			Ok(())
+		}));
+
+		schedule.run(&mut world)?;
+		schedule.run(&mut world)?;
+		assert_eq!(world.resources().borrow().get::<Total>(), Some(&Total(30)));
+
+		Ok(())
+	}
+}
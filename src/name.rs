@@ -0,0 +1,306 @@
+use self::error::DuplicateNameError;
+use crate::{
+	error::Result,
+	world::{Component, Entity, World},
+};
+use serde::{Deserialize, Serialize};
+use std::{any::TypeId, collections::HashMap};
+
+pub mod error {
+	use crate::world::Entity;
+
+	#[derive(Debug)]
+	pub struct DuplicateNameError {
+		pub name: String,
+		pub entity: Entity,
+	}
+
+	impl std::error::Error for DuplicateNameError {}
+
+	impl std::fmt::Display for DuplicateNameError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Name '{}' already belongs to entity {:?}.", self.name, self.entity)
+		}
+	}
+}
+
+/// A human readable entity name, indexed by the world so entities can be looked up by name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Name(pub String);
+
+impl Name {
+	pub fn new(name: impl Into<String>) -> Self {
+		Self(name.into())
+	}
+
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl From<&str> for Name {
+	fn from(name: &str) -> Self {
+		Self::new(name)
+	}
+}
+
+impl From<String> for Name {
+	fn from(name: String) -> Self {
+		Self(name)
+	}
+}
+
+impl std::fmt::Display for Name {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+/// What happens when a `Name` is added that another entity already has.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DuplicateNamePolicy {
+	/// Several entities may share a name. `find_by_name` returns the one named first.
+	///
+	/// Names written in place, for example through `get_component_mut`, are re-indexed on the next
+	/// lookup as if they were named then, and are not checked against the policy.
+	#[default]
+	Allow,
+
+	/// Adding a name that is already in use fails with a `DuplicateNameError`.
+	Reject,
+}
+
+/// Formats an entity together with its name, see `World::debug_entity`.
+pub struct EntityDebug<'a> {
+	world: &'a World,
+	entity: Entity,
+}
+
+impl std::fmt::Debug for EntityDebug<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let mut debug = f.debug_struct("Entity");
+		debug.field("index", self.entity.index()).field("generation", self.entity.generation());
+		if let Some(name) = self.world.get_component::<Name>(self.entity) {
+			debug.field("name", &name.0);
+		}
+		debug.finish()
+	}
+}
+
+impl World {
+	pub const fn set_duplicate_name_policy(&mut self, policy: DuplicateNamePolicy) {
+		self.duplicate_name_policy = policy;
+	}
+
+	pub const fn duplicate_name_policy(&self) -> DuplicateNamePolicy {
+		self.duplicate_name_policy
+	}
+
+	/// The entity named `name`. If several entities share the name, the one named first.
+	pub fn find_by_name(&self, name: &str) -> Option<Entity> {
+		self.refresh_indices();
+		self.names.borrow().get(name).and_then(|entities| entities.first().copied())
+	}
+
+	/// Every entity named `name`, in the order they were named.
+	pub fn find_all_by_name(&self, name: &str) -> Vec<Entity> {
+		self.refresh_indices();
+		self.names.borrow().get(name).cloned().unwrap_or_default()
+	}
+
+	/// Wrap `entity` so that its `Debug` output includes its `Name`.
+	pub const fn debug_entity(&self, entity: Entity) -> EntityDebug<'_> {
+		EntityDebug { world: self, entity }
+	}
+
+	/// Keep the name index in step with a `Name` being assigned to or removed from `entity`.
+	pub(crate) fn index_name(&mut self, entity: Entity, type_id: TypeId, value: Option<&Component>) -> Result<()> {
+		if type_id != TypeId::of::<Name>() {
+			return Ok(());
+		}
+
+		let name = value.and_then(|component| component.downcast_ref::<Name>()).map(|name| name.0.clone());
+		if let Some(name) = name.as_ref() {
+			self.refresh_indices();
+			let owner = self
+				.names
+				.get_mut()
+				.get(name)
+				.and_then(|entities| entities.iter().copied().find(|owner| *owner != entity));
+			if let (DuplicateNamePolicy::Reject, Some(owner)) = (self.duplicate_name_policy, owner) {
+				return Err(Box::new(DuplicateNameError {
+					name: name.clone(),
+					entity: owner,
+				}));
+			}
+		}
+
+		self.unindex_name(entity);
+		if let Some(name) = name {
+			self.names.get_mut().entry(name).or_default().push(entity);
+		}
+		Ok(())
+	}
+
+	pub(crate) fn unindex_name(&mut self, entity: Entity) {
+		let name = self.get_component::<Name>(entity).map(|name| name.0.clone());
+		let Some(name) = name else {
+			return;
+		};
+		let names = self.names.get_mut();
+		if let Some(entities) = names.get_mut(&name) {
+			entities.retain(|named| *named != entity);
+			if entities.is_empty() {
+				names.remove(&name);
+			}
+		}
+	}
+
+	/// Rebuild the name index from the `Name` column. Returns false without touching the index if
+	/// the column is borrowed mutably.
+	pub(crate) fn rebuild_names(&self) -> bool {
+		let Some(column) = self.components.get(&TypeId::of::<Name>()) else {
+			self.names.borrow_mut().clear();
+			return true;
+		};
+		let Ok(column) = column.try_borrow() else {
+			return false;
+		};
+		let mut names = HashMap::<String, Vec<Entity>>::new();
+		for entity in self.allocator.allocated_handles() {
+			if let Some(name) = column.get(entity).and_then(|name| name.downcast_ref::<Name>()) {
+				names.entry(name.0.clone()).or_default().push(entity);
+			}
+		}
+		*self.names.borrow_mut() = names;
+		self.stale_names.borrow_mut().clear();
+		true
+	}
+
+	/// Remember the name of `entity` before its `Name` is borrowed mutably, so that the next lookup
+	/// only has to move that entity within the index.
+	pub(crate) fn mark_name_borrowed(&self, entity: Entity) {
+		let Some(column) = self.components.get(&TypeId::of::<Name>()) else {
+			return;
+		};
+		let Ok(column) = column.try_borrow() else {
+			self.stale_indices.set(true);
+			return;
+		};
+		let name = column.get(entity).and_then(|name| name.downcast_ref::<Name>()).map(|name| name.0.clone());
+		self.stale_names.borrow_mut().entry(entity).or_insert(name);
+	}
+
+	/// Move the entities whose `Name` was borrowed mutably from their previous to their current
+	/// name. Does nothing while the column is borrowed mutably.
+	pub(crate) fn reindex_stale_names(&self) {
+		if self.stale_names.borrow().is_empty() {
+			return;
+		}
+		let Some(column) = self.components.get(&TypeId::of::<Name>()) else {
+			return;
+		};
+		let Ok(column) = column.try_borrow() else {
+			return;
+		};
+
+		let mut stale = self.stale_names.borrow_mut().drain().collect::<Vec<_>>();
+		stale.sort_unstable_by_key(|(entity, _)| *entity.index());
+		let mut names = self.names.borrow_mut();
+		for (entity, previous) in stale {
+			if let Some(previous) = previous {
+				if let Some(entities) = names.get_mut(&previous) {
+					entities.retain(|named| *named != entity);
+					if entities.is_empty() {
+						names.remove(&previous);
+					}
+				}
+			}
+			if let Some(name) = column.get(entity).and_then(|name| name.downcast_ref::<Name>()) {
+				names.entry(name.0.clone()).or_default().push(entity);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn find_by_name() -> Result<()> {
+		let mut world = World::new();
+		let entities = world.create_entities(3);
+		world.add_component(entities[0], Name::new("player"))?;
+		world.add_component(entities[1], Name::new("camera_main"))?;
+		world.add_component(entities[2], Name::new("player"))?;
+
+		assert_eq!(world.find_by_name("camera_main"), Some(entities[1]));
+		assert_eq!(world.find_all_by_name("player"), &[entities[0], entities[2]]);
+
+		world.add_component(entities[0], Name::new("enemy"))?;
+		assert_eq!(world.find_by_name("player"), Some(entities[2]));
+
+		world.remove_entity(entities[2]);
+		assert_eq!(world.find_by_name("player"), None);
+		assert_eq!(world.find_by_name("enemy"), Some(entities[0]));
+
+		world.remove_component::<Name>(entities[0])?;
+		assert_eq!(world.find_by_name("enemy"), None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn find_spawned_and_renamed_entities() -> Result<()> {
+		let mut world = World::new();
+		let player = world.spawn((Name::new("player"),))?;
+		let enemies = world.spawn_batch(vec![(Name::new("enemy"),), (Name::new("enemy"),)])?;
+
+		assert_eq!(world.find_by_name("player"), Some(player));
+		assert_eq!(world.find_all_by_name("enemy"), enemies);
+
+		*world.get_component_mut::<Name>(enemies[1]).unwrap() = Name::new("boss");
+		assert_eq!(world.find_by_name("boss"), Some(enemies[1]));
+		assert_eq!(world.find_all_by_name("enemy"), &[enemies[0]]);
+
+		*world.get_component_mut::<Name>(enemies[0]).unwrap() = Name::new("scout");
+		*world.get_component_mut::<Name>(enemies[0]).unwrap() = Name::new("boss");
+		assert_eq!(world.find_all_by_name("boss"), &[enemies[1], enemies[0]]);
+		assert_eq!(world.find_by_name("scout"), None);
+		assert_eq!(world.find_by_name("enemy"), None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn reject_duplicate_names() -> Result<()> {
+		let mut world = World::new();
+		world.set_duplicate_name_policy(DuplicateNamePolicy::Reject);
+		let entities = world.create_entities(2);
+		world.add_component(entities[0], Name::new("player"))?;
+		world.add_component(entities[0], Name::new("player"))?;
+
+		assert!(world.add_component(entities[1], Name::new("player")).is_err());
+		assert!(world.get_component::<Name>(entities[1]).is_none());
+		assert_eq!(world.find_all_by_name("player"), &[entities[0]]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn debug_entity() -> Result<()> {
+		let mut world = World::new();
+		let entities = world.create_entities(2);
+		world.add_component(entities[1], Name::new("player"))?;
+
+		assert_eq!(format!("{:?}", world.debug_entity(entities[0])), "Entity { index: 0, generation: 0 }");
+		assert_eq!(
+			format!("{:?}", world.debug_entity(entities[1])),
+			r#"Entity { index: 1, generation: 0, name: "player" }"#
+		);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,205 @@
+use self::error::NotPooledError;
+use crate::{
+	bundle::{check_bundle, Bundle},
+	error::Result,
+	vec::error::HandleNotFoundError,
+	world::{Component, Entity, World},
+};
+use std::{
+	any::{Any, TypeId},
+	collections::HashSet,
+};
+
+pub mod error {
+	use crate::world::Entity;
+
+	#[derive(Debug)]
+	pub struct NotPooledError {
+		pub entity: Entity,
+	}
+
+	impl std::error::Error for NotPooledError {}
+
+	impl std::fmt::Display for NotPooledError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Entity {:?} is not an acquired entity of this pool.", self.entity)
+		}
+	}
+}
+
+type ResetFn = Box<dyn Fn(&mut dyn Any)>;
+
+/// A fixed set of component bundles that are handed out as entities and taken back, for objects
+/// such as bullets and particles that are created and destroyed often.
+///
+/// The components are boxed once, when the pool is created. Acquiring an entity allocates a handle
+/// from the `HandleAllocator` free list and moves a set of components into it. Releasing it moves
+/// the components back into the pool and returns the handle to the free list, so the next acquire
+/// usually reuses the same index with a newer generation.
+pub struct EntityPool {
+	available: Vec<Vec<(TypeId, Component)>>,
+	acquired: HashSet<Entity>,
+	type_ids: Vec<TypeId>,
+	resets: Vec<(TypeId, ResetFn)>,
+}
+
+impl EntityPool {
+	/// Box `capacity` copies of the components of `bundle`.
+	pub fn new<B: Bundle + Clone>(world: &mut World, capacity: usize, bundle: B) -> Result<Self> {
+		let type_ids = check_bundle::<B>()?.into_iter().map(|(type_id, _)| type_id).collect();
+		B::register(world);
+		Ok(Self {
+			available: (0..capacity).map(|_| bundle.clone().into_components()).collect(),
+			acquired: HashSet::new(),
+			type_ids,
+			resets: Vec::new(),
+		})
+	}
+
+	/// Reset components of type `T` in place whenever an entity is released.
+	pub fn with_reset<T: 'static>(mut self, reset: impl Fn(&mut T) + 'static) -> Self {
+		let reset = move |component: &mut dyn Any| {
+			if let Some(component) = component.downcast_mut::<T>() {
+				reset(component);
+			}
+		};
+		self.resets.push((TypeId::of::<T>(), Box::new(reset)));
+		self
+	}
+
+	/// Create an entity holding a released set of components, or `None` if every set is in use.
+	///
+	/// Components such as `StableId` and `Name` are indexed as usual. If a component cannot be
+	/// assigned, for example because it conflicts with another entity, the entity is removed again
+	/// and the set stays in the pool.
+	pub fn acquire(&mut self, world: &mut World) -> Result<Option<Entity>> {
+		let Some(components) = self.available.pop() else {
+			return Ok(None);
+		};
+
+		let entity = world.create_entity();
+		let mut components = components.into_iter();
+		while let Some((type_id, component)) = components.next() {
+			match world.prepare_component(entity, type_id, Some(&component)) {
+				Ok(column) => world.store_component(&column, entity, type_id, Some(component)),
+				Err(error) => {
+					let mut set = self.take_components(world, entity);
+					set.push((type_id, component));
+					set.extend(components);
+					world.remove_entity(entity);
+					self.available.push(set);
+					return Err(error);
+				},
+			}
+		}
+
+		self.acquired.insert(entity);
+		Ok(Some(entity))
+	}
+
+	/// Move the pooled components out of an acquired entity, reset them and remove the entity.
+	///
+	/// Components added to the entity after it was acquired are dropped. If a pooled component was
+	/// removed from the entity, or the entity was removed from the world, its set is not returned
+	/// to the pool.
+	pub fn release(&mut self, world: &mut World, entity: Entity) -> Result<()> {
+		if !self.acquired.remove(&entity) {
+			return Err(Box::new(NotPooledError { entity }));
+		}
+		if !world.entity_exists(entity) {
+			return Err(Box::new(HandleNotFoundError { handle: entity }));
+		}
+
+		let mut components = self.take_components(world, entity);
+		world.remove_entity(entity);
+		for (type_id, component) in components.iter_mut() {
+			for (_, reset) in self.resets.iter().filter(|(reset_type_id, _)| reset_type_id == type_id) {
+				reset(component.as_mut());
+			}
+		}
+		if components.len() == self.type_ids.len() {
+			self.available.push(components);
+		}
+		Ok(())
+	}
+
+	fn take_components(&self, world: &mut World, entity: Entity) -> Vec<(TypeId, Component)> {
+		self.type_ids
+			.iter()
+			.filter_map(|type_id| world.take_component_by_id(entity, *type_id).map(|component| (*type_id, component)))
+			.collect()
+	}
+
+	/// The number of entities that can be acquired.
+	pub fn available(&self) -> usize {
+		self.available.len()
+	}
+
+	/// The number of entities currently handed out.
+	pub fn acquired(&self) -> usize {
+		self.acquired.len()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::name::{DuplicateNamePolicy, Name};
+
+	#[derive(Debug, Default, PartialEq, Copy, Clone)]
+	struct Position {
+		x: f32,
+		y: f32,
+	}
+
+	#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+	struct Lifetime(u32);
+
+	#[test]
+	fn acquire_and_release() -> Result<()> {
+		let mut world = World::new();
+		let mut pool = EntityPool::new(&mut world, 2, (Position::default(), Lifetime(60)))?
+			.with_reset::<Position>(|position| *position = Position::default())
+			.with_reset::<Lifetime>(|lifetime| lifetime.0 = 60);
+		assert!(world.allocator().allocated_handles().is_empty());
+
+		let first = pool.acquire(&mut world)?.unwrap();
+		let second = pool.acquire(&mut world)?.unwrap();
+		assert_eq!(pool.acquire(&mut world)?, None);
+		assert_eq!(world.enabled_entities(), vec![first, second]);
+
+		world.get_component_mut::<Position>(first).unwrap().x = 5.0;
+		world.get_component_mut::<Lifetime>(first).unwrap().0 = 3;
+		pool.release(&mut world, first)?;
+		assert!(pool.release(&mut world, first).is_err());
+		assert!(!world.entity_exists(first));
+		assert_eq!(pool.available(), 1);
+		assert_eq!(pool.acquired(), 1);
+
+		let third = pool.acquire(&mut world)?.unwrap();
+		assert_eq!((third.index(), *third.generation()), (first.index(), first.generation() + 1));
+		assert_eq!(world.get_component::<Position>(third).as_deref(), Some(&Position::default()));
+		assert_eq!(world.get_component::<Lifetime>(third).as_deref(), Some(&Lifetime(60)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn pooled_components_are_indexed() -> Result<()> {
+		let mut world = World::new();
+		world.set_duplicate_name_policy(DuplicateNamePolicy::Reject);
+		let mut pool = EntityPool::new(&mut world, 2, (Name::new("bullet"), Lifetime(1)))?;
+
+		let bullet = pool.acquire(&mut world)?.unwrap();
+		assert_eq!(world.find_by_name("bullet"), Some(bullet));
+		assert!(pool.acquire(&mut world).is_err());
+		assert_eq!(pool.available(), 1);
+		assert_eq!(world.allocator().allocated_handles(), vec![bullet]);
+
+		pool.release(&mut world, bullet)?;
+		assert_eq!(world.find_by_name("bullet"), None);
+		assert_eq!(pool.available(), 2);
+
+		Ok(())
+	}
+}
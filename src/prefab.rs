@@ -0,0 +1,318 @@
+use self::error::{PrefabCycleError, PrefabNotFoundError, UnknownComponentError};
+use crate::{
+	error::Result,
+	hierarchy::{Children, Parent},
+	vec::error::HandleNotFoundError,
+	world::{Entity, World},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+pub mod error {
+	#[derive(Debug)]
+	pub struct PrefabNotFoundError {
+		pub name: String,
+	}
+
+	impl std::error::Error for PrefabNotFoundError {}
+
+	impl std::fmt::Display for PrefabNotFoundError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Prefab '{}' does not exist.", self.name)
+		}
+	}
+
+	#[derive(Debug)]
+	pub struct PrefabCycleError {
+		pub name: String,
+	}
+
+	impl std::error::Error for PrefabCycleError {}
+
+	impl std::fmt::Display for PrefabCycleError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Prefab '{}' inherits from itself.", self.name)
+		}
+	}
+
+	#[derive(Debug)]
+	pub struct UnknownComponentError {
+		pub name: String,
+	}
+
+	impl std::error::Error for UnknownComponentError {}
+
+	impl std::fmt::Display for UnknownComponentError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Component '{}' was not registered as serializable.", self.name)
+		}
+	}
+}
+
+/// An entity template: components keyed by their registered name, an optional template to inherit
+/// from and the templates of any child entities.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prefab {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub extends: Option<String>,
+
+	#[serde(default)]
+	pub components: BTreeMap<String, Value>,
+
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub children: Vec<Self>,
+}
+
+/// Named prefabs, usually loaded from a data file and stored as a resource.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PrefabLibrary {
+	prefabs: BTreeMap<String, Prefab>,
+}
+
+impl PrefabLibrary {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Parse a library from a JSON object mapping prefab names to prefabs.
+	pub fn from_json(json: &str) -> Result<Self> {
+		trace_span!("prefab_library_from_json", bytes = json.len());
+		Ok(serde_json::from_str(json)?)
+	}
+
+	pub fn insert(&mut self, name: impl Into<String>, prefab: Prefab) {
+		self.prefabs.insert(name.into(), prefab);
+	}
+
+	pub fn get(&self, name: &str) -> Option<&Prefab> {
+		self.prefabs.get(name)
+	}
+
+	/// Flatten the inheritance chain of the prefab `name` and of all of its children.
+	///
+	/// Components of a derived prefab override those of its base. When both values are JSON
+	/// objects they are merged field by field, otherwise the derived value replaces the base value.
+	/// Children of the base prefab come before the children of the derived prefab.
+	pub fn resolve(&self, name: &str) -> Result<Prefab> {
+		self.resolve_named(name, &mut Vec::new())
+	}
+
+	fn resolve_named(&self, name: &str, visiting: &mut Vec<String>) -> Result<Prefab> {
+		if visiting.iter().any(|visited| visited == name) {
+			return Err(Box::new(PrefabCycleError { name: name.to_string() }));
+		}
+		let prefab = self.prefabs.get(name).ok_or_else(|| PrefabNotFoundError { name: name.to_string() })?;
+		visiting.push(name.to_string());
+		let resolved = self.resolve_prefab(prefab, visiting);
+		visiting.pop();
+		resolved
+	}
+
+	fn resolve_prefab(&self, prefab: &Prefab, visiting: &mut Vec<String>) -> Result<Prefab> {
+		let mut resolved = match prefab.extends.as_deref() {
+			Some(base) => self.resolve_named(base, visiting)?,
+			None => Prefab::default(),
+		};
+
+		for (name, value) in prefab.components.iter() {
+			match resolved.components.get_mut(name) {
+				Some(base) => merge_values(base, value),
+				None => {
+					resolved.components.insert(name.clone(), value.clone());
+				},
+			}
+		}
+
+		for child in prefab.children.iter() {
+			let child = self.resolve_prefab(child, visiting)?;
+			resolved.children.push(child);
+		}
+
+		Ok(resolved)
+	}
+}
+
+fn merge_values(base: &mut Value, overrides: &Value) {
+	match (base, overrides) {
+		(Value::Object(base), Value::Object(overrides)) => {
+			for (key, value) in overrides.iter() {
+				match base.get_mut(key) {
+					Some(existing) => merge_values(existing, value),
+					None => {
+						base.insert(key.clone(), value.clone());
+					},
+				}
+			}
+		},
+		(base, overrides) => *base = overrides.clone(),
+	}
+}
+
+impl World {
+	/// Spawn the prefab `name` from the `PrefabLibrary` resource.
+	///
+	/// Children are spawned as separate entities linked through the `Parent` and `Children`
+	/// components. If any component fails to deserialize, every entity spawned so far is removed.
+	pub fn spawn_prefab(&mut self, name: &str) -> Result<Entity> {
+		trace_span!("spawn_prefab", name);
+		let prefab = {
+			let resources = self.resources.borrow();
+			let library = resources.get::<PrefabLibrary>().ok_or_else(|| PrefabNotFoundError { name: name.to_string() })?;
+			library.resolve(name)?
+		};
+
+		let mut spawned = Vec::new();
+		let result = self.instantiate_prefab(&prefab, &mut spawned);
+		if result.is_err() {
+			self.remove_entities(&spawned);
+		}
+		result
+	}
+
+	/// Capture the serializable components of `entity` as a prefab.
+	/// Components that were not registered as serializable are skipped.
+	pub fn entity_to_prefab(&self, entity: Entity) -> Result<Prefab> {
+		trace_span!("entity_to_prefab", entity.index = *entity.index(), entity.generation = *entity.generation());
+		if !self.entity_exists(entity) {
+			return Err(Box::new(HandleNotFoundError { handle: entity }));
+		}
+
+		let mut components = BTreeMap::new();
+		for (type_id, component_vec) in self.components.iter() {
+			let Some(name) = self.registry.component(type_id).and_then(|registration| registration.serialized_name()) else {
+				continue;
+			};
+			if let Some(component) = component_vec.borrow().get(entity) {
+				let value = self
+					.registry
+					.serialize_component(type_id, component)
+					.expect("Serializable component has no serializer")?;
+				components.insert(name.to_string(), value);
+			}
+		}
+
+		Ok(Prefab { components, ..Prefab::default() })
+	}
+
+	fn instantiate_prefab(&mut self, prefab: &Prefab, spawned: &mut Vec<Entity>) -> Result<Entity> {
+		let entity = self.create_entity();
+		spawned.push(entity);
+
+		for (name, value) in prefab.components.iter() {
+			let (type_id, component) = match self
+				.registry
+				.component_by_name(name)
+				.and_then(|type_id| self.registry.deserialize_component(&type_id, value).map(|component| (type_id, component)))
+			{
+				Some((type_id, component)) => (type_id, component?),
+				None => return Err(Box::new(UnknownComponentError { name: name.clone() })),
+			};
+			self.assign_component_by_id(entity, type_id, Some(component))?;
+		}
+
+		let mut children = Vec::new();
+		for child in prefab.children.iter() {
+			let child = self.instantiate_prefab(child, spawned)?;
+			self.add_component(child, Parent(entity))?;
+			children.push(child);
+		}
+		if !children.is_empty() {
+			self.add_component(entity, Children(children))?;
+		}
+
+		Ok(entity)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::name::Name;
+
+	#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+	struct Position {
+		x: f32,
+		y: f32,
+	}
+
+	#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+	struct Health(u8);
+
+	const PREFABS: &str = r#"{
+		"Creature": {
+			"components": {
+				"Position": { "x": 1.0, "y": 2.0 },
+				"Health": 10
+			}
+		},
+		"Goblin": {
+			"extends": "Creature",
+			"components": {
+				"Position": { "y": 5.0 },
+				"Health": 30,
+				"Name": "Goblin"
+			},
+			"children": [
+				{ "components": { "Name": "Dagger" } }
+			]
+		},
+		"Ouroboros": { "extends": "Ouroboros" }
+	}"#;
+
+	fn world() -> Result<World> {
+		let mut world = World::new();
+		world.register_serializable_component::<Position>("Position");
+		world.register_serializable_component::<Health>("Health");
+		world.register_serializable_component::<Name>("Name");
+		world.resources().borrow_mut().insert(PrefabLibrary::from_json(PREFABS)?);
+		Ok(world)
+	}
+
+	#[test]
+	fn spawn_prefab() -> Result<()> {
+		let mut world = world()?;
+		let goblin = world.spawn_prefab("Goblin")?;
+
+		assert_eq!(world.get_component::<Position>(goblin).as_deref(), Some(&Position { x: 1.0, y: 5.0 }));
+		assert_eq!(world.get_component::<Health>(goblin).as_deref(), Some(&Health(30)));
+		assert_eq!(world.get_component::<Name>(goblin).as_deref(), Some(&Name("Goblin".to_string())));
+
+		let children = world.get_component::<Children>(goblin).unwrap().0.clone();
+		assert_eq!(children.len(), 1);
+		assert_eq!(world.get_component::<Name>(children[0]).as_deref(), Some(&Name("Dagger".to_string())));
+		assert_eq!(world.get_component::<Parent>(children[0]).as_deref(), Some(&Parent(goblin)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn prefab_errors() -> Result<()> {
+		let mut world = world()?;
+		assert!(world.spawn_prefab("Dragon").is_err());
+		assert!(world.spawn_prefab("Ouroboros").is_err());
+
+		world
+			.resources()
+			.borrow_mut()
+			.insert(PrefabLibrary::from_json(r#"{ "Ghost": { "components": { "Position": {}, "Ectoplasm": 3 } } }"#)?);
+		assert!(world.spawn_prefab("Ghost").is_err());
+		assert!(world.allocator.allocated_handles().is_empty());
+
+		Ok(())
+	}
+
+	#[test]
+	fn entity_to_prefab() -> Result<()> {
+		let mut world = world()?;
+		let entity = world.create_entity();
+		world.add_component(entity, Health(12))?;
+		world.add_component(entity, 3_u32)?;
+
+		let prefab = world.entity_to_prefab(entity)?;
+		assert_eq!(prefab.components, BTreeMap::from([("Health".to_string(), serde_json::json!(12))]));
+
+		Ok(())
+	}
+}
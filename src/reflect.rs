@@ -0,0 +1,356 @@
+use self::error::{MissingComponentError, NotReflectableError, UnknownFieldError};
+use crate::{
+	error::Result,
+	vec::error::HandleNotFoundError,
+	world::{Entity, World},
+};
+pub use parsecs_derive::Reflect;
+pub use serde_json::Value;
+use std::{
+	cell::RefMut,
+	ops::{Deref, DerefMut},
+};
+
+pub mod error {
+	use crate::world::Entity;
+
+	#[derive(Debug)]
+	pub struct NotReflectableError {
+		pub name: String,
+	}
+
+	impl std::error::Error for NotReflectableError {}
+
+	impl std::fmt::Display for NotReflectableError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Component '{}' was not registered for reflection.", self.name)
+		}
+	}
+
+	#[derive(Debug)]
+	pub struct MissingComponentError {
+		pub entity: Entity,
+		pub name: String,
+	}
+
+	impl std::error::Error for MissingComponentError {}
+
+	impl std::fmt::Display for MissingComponentError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Entity {:?} has no '{}' component.", self.entity, self.name)
+		}
+	}
+
+	#[derive(Debug)]
+	pub struct UnknownFieldError {
+		pub type_name: &'static str,
+		pub field: String,
+	}
+
+	impl std::error::Error for UnknownFieldError {}
+
+	impl std::fmt::Display for UnknownFieldError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Type '{}' has no field '{}'.", self.type_name, self.field)
+		}
+	}
+}
+
+/// Access to the fields of a value by name, without knowing its type at compile time.
+///
+/// Implemented for primitive types and `String`. Structs derive it, or implement it with
+/// `impl_reflect!` naming the fields to expose.
+///
+/// ```
+/// use parsecs::reflect::Reflect;
+///
+/// #[derive(Reflect)]
+/// struct Position {
+///     x: f32,
+///     y: f32,
+///     #[reflect(skip)]
+///     dirty: bool,
+/// }
+/// ```
+pub trait Reflect: 'static {
+	fn type_name(&self) -> &'static str {
+		std::any::type_name::<Self>()
+	}
+
+	/// The names of the fields exposed by `field` and `field_mut`, empty for primitive values.
+	fn field_names(&self) -> &'static [&'static str] {
+		&[]
+	}
+
+	fn field(&self, _name: &str) -> Option<&dyn Reflect> {
+		None
+	}
+
+	fn field_mut(&mut self, _name: &str) -> Option<&mut dyn Reflect> {
+		None
+	}
+
+	fn to_value(&self) -> Result<Value>;
+
+	fn set_value(&mut self, value: Value) -> Result<()>;
+}
+
+macro_rules! impl_reflect_value {
+	($($type:ty),*) => {
+		$(
+			impl Reflect for $type {
+				fn to_value(&self) -> Result<Value> {
+					Ok(serde_json::to_value(self)?)
+				}
+
+				fn set_value(&mut self, value: Value) -> Result<()> {
+					*self = serde_json::from_value(value)?;
+					Ok(())
+				}
+			}
+		)*
+	};
+}
+
+impl_reflect_value!(bool, char, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64, String);
+
+/// Implement `Reflect` for a struct by listing the fields to expose. Every listed field must
+/// implement `Reflect` itself.
+///
+/// ```
+/// use parsecs::impl_reflect;
+///
+/// struct Position {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// impl_reflect!(Position { x, y });
+/// ```
+#[macro_export]
+macro_rules! impl_reflect {
+	($type:ty { $($field:ident),+ $(,)? }) => {
+		impl $crate::reflect::Reflect for $type {
+			fn field_names(&self) -> &'static [&'static str] {
+				&[$(stringify!($field)),+]
+			}
+
+			fn field(&self, name: &str) -> Option<&dyn $crate::reflect::Reflect> {
+				match name {
+					$(stringify!($field) => Some(&self.$field),)+
+					_ => None,
+				}
+			}
+
+			fn field_mut(&mut self, name: &str) -> Option<&mut dyn $crate::reflect::Reflect> {
+				match name {
+					$(stringify!($field) => Some(&mut self.$field),)+
+					_ => None,
+				}
+			}
+
+			fn to_value(&self) -> $crate::error::Result<$crate::reflect::Value> {
+				$crate::reflect::fields_to_value(self)
+			}
+
+			fn set_value(&mut self, value: $crate::reflect::Value) -> $crate::error::Result<()> {
+				$crate::reflect::set_fields(self, value)
+			}
+		}
+	};
+}
+
+/// Collect the fields of a reflected struct into a JSON object.
+pub fn fields_to_value(reflect: &dyn Reflect) -> Result<Value> {
+	let mut object = serde_json::Map::new();
+	for name in reflect.field_names() {
+		if let Some(field) = reflect.field(name) {
+			object.insert((*name).to_string(), field.to_value()?);
+		}
+	}
+	Ok(Value::Object(object))
+}
+
+/// Set the fields of a reflected struct from a JSON object. Fields missing from the object are
+/// left unchanged. If any field cannot be set, the struct is left as it was.
+pub fn set_fields(reflect: &mut dyn Reflect, value: Value) -> Result<()> {
+	let Value::Object(object) = value else {
+		return Err(format!("Expected an object to set the fields of '{}'.", reflect.type_name()).into());
+	};
+	let type_name = reflect.type_name();
+	if let Some(field) = object.keys().find(|name| reflect.field(name).is_none()) {
+		return Err(Box::new(UnknownFieldError { type_name, field: field.clone() }));
+	}
+
+	let mut previous = Vec::<(String, Value)>::new();
+	for (name, value) in object {
+		let field = reflect.field_mut(&name).unwrap();
+		let previous_value = field.to_value()?;
+		if let Err(error) = field.set_value(value) {
+			for (name, value) in previous.into_iter().rev() {
+				reflect.field_mut(&name).unwrap().set_value(value)?;
+			}
+			return Err(error);
+		}
+		previous.push((name, previous_value));
+	}
+	Ok(())
+}
+
+/// A reflected component or field, borrowed mutably from its component column.
+pub struct ReflectMut<'a> {
+	value: RefMut<'a, dyn Reflect>,
+}
+
+impl<'a> ReflectMut<'a> {
+	/// Narrow the borrow down to the field `name`.
+	pub fn field(self, name: &str) -> Result<Self> {
+		match RefMut::filter_map(self.value, |value| value.field_mut(name)) {
+			Ok(value) => Ok(Self { value }),
+			Err(value) => Err(Box::new(UnknownFieldError {
+				type_name: value.type_name(),
+				field: name.to_string(),
+			})),
+		}
+	}
+
+	pub fn get(&self) -> Result<Value> {
+		self.value.to_value()
+	}
+
+	pub fn set(&mut self, value: impl Into<Value>) -> Result<()> {
+		self.value.set_value(value.into())
+	}
+}
+
+impl Deref for ReflectMut<'_> {
+	type Target = dyn Reflect;
+
+	fn deref(&self) -> &Self::Target {
+		&*self.value
+	}
+}
+
+impl DerefMut for ReflectMut<'_> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut *self.value
+	}
+}
+
+impl World {
+	/// Make the component type `T` reachable through `get_reflect` under `name`.
+	pub fn register_reflect_component<T: Reflect>(&mut self, name: &'static str) {
+		self.register_component::<T>();
+		self.registry.register_reflect_component::<T>(name);
+	}
+
+	/// Borrow the component registered for reflection as `name` from `entity`.
+	pub fn get_reflect(&self, entity: Entity, name: &str) -> Result<ReflectMut<'_>> {
+		let (type_id, reflect_mut) = self
+			.registry
+			.component_by_reflect_name(name)
+			.and_then(|type_id| {
+				self.registry
+					.component(&type_id)
+					.and_then(|registration| registration.reflect_mut())
+					.map(|reflect_mut| (type_id, reflect_mut))
+			})
+			.ok_or_else(|| NotReflectableError { name: name.to_string() })?;
+		if !self.entity_exists(entity) {
+			return Err(Box::new(HandleNotFoundError { handle: entity }));
+		}
+
+		let column = self
+			.components
+			.get(&type_id)
+			.filter(|column| column.borrow().get(entity).is_some())
+			.ok_or_else(|| MissingComponentError { entity, name: name.to_string() })?;
+		self.mark_component_borrowed(entity, type_id);
+		let value = RefMut::map(column.borrow_mut(), |column| reflect_mut(column.get_mut(entity).unwrap().as_mut()));
+		Ok(ReflectMut { value })
+	}
+
+	/// The reflection names of the components `entity` holds, in alphabetical order.
+	pub fn reflect_components(&self, entity: Entity) -> Vec<&'static str> {
+		let mut names = self
+			.components
+			.iter()
+			.filter(|(_, column)| column.borrow().get(entity).is_some())
+			.filter_map(|(type_id, _)| self.registry.component(type_id).and_then(|registration| registration.reflect_name()))
+			.collect::<Vec<_>>();
+		names.sort_unstable();
+		names
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Debug, Default, PartialEq, Clone)]
+	struct Position {
+		x: f32,
+		y: f32,
+	}
+
+	impl_reflect!(Position { x, y });
+
+	#[derive(Debug, Default, PartialEq, Clone, Reflect)]
+	struct Player {
+		name: String,
+		position: Position,
+		lives: u8,
+		#[reflect(skip)]
+		score: u32,
+	}
+
+	#[test]
+	fn set_fields_by_name() -> Result<()> {
+		let mut world = World::new();
+		world.register_reflect_component::<Position>("Position");
+		let entity = world.create_entity();
+		world.add_component(entity, Position::default())?;
+
+		world.get_reflect(entity, "Position")?.field("x")?.set(3.0)?;
+		assert_eq!(world.get_component::<Position>(entity).as_deref(), Some(&Position { x: 3.0, y: 0.0 }));
+		assert_eq!(world.get_reflect(entity, "Position")?.get()?, serde_json::json!({ "x": 3.0, "y": 0.0 }));
+
+		assert!(world.get_reflect(entity, "Position")?.field("z").is_err());
+		assert!(world.get_reflect(entity, "Position")?.field("x")?.set("three").is_err());
+		assert!(world.get_reflect(entity, "Velocity").is_err());
+		let other = world.create_entity();
+		assert!(world.get_reflect(other, "Position").is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn nested_fields() -> Result<()> {
+		let mut world = World::new();
+		world.register_reflect_component::<Player>("Player");
+		world.register_reflect_component::<Position>("Position");
+		let entity = world.create_entity();
+		world.add_component(entity, Player::default())?;
+
+		world.get_reflect(entity, "Player")?.field("position")?.field("y")?.set(2.0)?;
+		world.get_reflect(entity, "Player")?.set(serde_json::json!({ "name": "Elliot", "lives": 3 }))?;
+
+		let player = world.get_component::<Player>(entity).unwrap().clone();
+		assert_eq!(
+			player,
+			Player {
+				name: "Elliot".to_string(),
+				position: Position { x: 0.0, y: 2.0 },
+				lives: 3,
+				score: 0,
+			}
+		);
+		assert!(world.get_reflect(entity, "Player")?.set(serde_json::json!({ "lives": 1, "name": 7 })).is_err());
+		assert!(world.get_reflect(entity, "Player")?.set(serde_json::json!({ "lives": 1, "score": 7 })).is_err());
+		assert_eq!(world.get_component::<Player>(entity).unwrap().lives, 3);
+		assert_eq!(world.get_reflect(entity, "Player")?.field_names(), &["name", "position", "lives"]);
+		assert_eq!(world.reflect_components(entity), vec!["Player"]);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,331 @@
+use crate::{
+	error::Result,
+	hash::StableHasher,
+	reflect::Reflect,
+	scene::{EntityMap, MapEntities},
+	world::Component,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+	any::{Any, TypeId},
+	collections::HashMap,
+	hash::Hash,
+};
+
+pub type CloneFn = fn(&dyn Any) -> Box<dyn Any>;
+pub type HashFn = fn(&dyn Any, &mut StableHasher);
+pub type SerializeFn = fn(&dyn Any) -> Result<Value>;
+pub type DeserializeFn = fn(&Value) -> Result<Component>;
+pub type MapEntitiesFn = fn(&mut dyn Any, &EntityMap) -> Result<()>;
+pub type ReflectMutFn = fn(&mut dyn Any) -> &mut dyn Reflect;
+
+fn clone_boxed<T: Clone + 'static>(value: &dyn Any) -> Box<dyn Any> {
+	Box::new(
+		value
+			.downcast_ref::<T>()
+			.expect("Registered clone function received a value of the wrong type")
+			.clone(),
+	)
+}
+
+fn hash_boxed<T: Hash + 'static>(value: &dyn Any, hasher: &mut StableHasher) {
+	value
+		.downcast_ref::<T>()
+		.expect("Registered hash function received a value of the wrong type")
+		.hash(hasher);
+}
+
+fn serialize_boxed<T: Serialize + 'static>(value: &dyn Any) -> Result<Value> {
+	Ok(serde_json::to_value(
+		value.downcast_ref::<T>().expect("Registered serialize function received a value of the wrong type"),
+	)?)
+}
+
+fn deserialize_boxed<T: DeserializeOwned + 'static>(value: &Value) -> Result<Component> {
+	Ok(Box::new(T::deserialize(value)?))
+}
+
+fn map_entities_boxed<T: MapEntities + 'static>(value: &mut dyn Any, entity_map: &EntityMap) -> Result<()> {
+	value
+		.downcast_mut::<T>()
+		.expect("Registered entity mapping function received a value of the wrong type")
+		.map_entities(entity_map)
+}
+
+fn reflect_mut_boxed<T: Reflect>(value: &mut dyn Any) -> &mut dyn Reflect {
+	value.downcast_mut::<T>().expect("Registered reflect function received a value of the wrong type")
+}
+
+#[derive(Clone, Copy)]
+struct ReflectRegistration {
+	name: &'static str,
+	reflect_mut: ReflectMutFn,
+}
+
+#[derive(Clone, Copy)]
+struct HashRegistration {
+	name: &'static str,
+	hash: HashFn,
+}
+
+#[derive(Clone, Copy)]
+struct SerdeRegistration {
+	name: &'static str,
+	serialize: SerializeFn,
+	deserialize: DeserializeFn,
+}
+
+#[derive(Clone, Copy)]
+pub struct ComponentRegistration {
+	name: &'static str,
+	clone: Option<CloneFn>,
+	hash: Option<HashRegistration>,
+	serde: Option<SerdeRegistration>,
+	map_entities: Option<MapEntitiesFn>,
+	reflect: Option<ReflectRegistration>,
+}
+
+impl ComponentRegistration {
+	pub fn of<T: 'static>() -> Self {
+		Self {
+			name: std::any::type_name::<T>(),
+			clone: None,
+			hash: None,
+			serde: None,
+			map_entities: None,
+			reflect: None,
+		}
+	}
+
+	pub const fn name(&self) -> &'static str {
+		self.name
+	}
+
+	pub const fn is_cloneable(&self) -> bool {
+		self.clone.is_some()
+	}
+
+	pub const fn is_hashable(&self) -> bool {
+		self.hash.is_some()
+	}
+
+	/// The name that orders the component in world state hashes, if it was registered as hashable.
+	pub fn hash_name(&self) -> Option<&'static str> {
+		self.hash.map(|hash| hash.name)
+	}
+
+	/// The name the component is known by in data files, if it was registered as serializable.
+	pub fn serialized_name(&self) -> Option<&'static str> {
+		self.serde.map(|serde| serde.name)
+	}
+
+	/// The name the component is known by to `World::get_reflect`, if it was registered for
+	/// reflection.
+	pub fn reflect_name(&self) -> Option<&'static str> {
+		self.reflect.map(|reflect| reflect.name)
+	}
+
+	pub fn reflect_mut(&self) -> Option<ReflectMutFn> {
+		self.reflect.map(|reflect| reflect.reflect_mut)
+	}
+}
+
+#[derive(Clone, Copy)]
+pub struct ResourceRegistration {
+	name: &'static str,
+	clone: CloneFn,
+}
+
+impl ResourceRegistration {
+	pub const fn name(&self) -> &'static str {
+		self.name
+	}
+}
+
+/// Type-erased operations for the component and resource types known to a `World`.
+#[derive(Default, Clone)]
+pub struct TypeRegistry {
+	components: HashMap<TypeId, ComponentRegistration>,
+	resources: HashMap<TypeId, ResourceRegistration>,
+	serialized_names: HashMap<&'static str, TypeId>,
+	reflect_names: HashMap<&'static str, TypeId>,
+}
+
+impl TypeRegistry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Retrieve the registration for the component type `T`, creating it if it does not exist yet.
+	pub fn component_entry<T: 'static>(&mut self) -> &mut ComponentRegistration {
+		self.components.entry(TypeId::of::<T>()).or_insert_with(ComponentRegistration::of::<T>)
+	}
+
+	/// Copy a registration from another registry, unless the type is already registered here.
+	pub fn merge_component_registration(&mut self, type_id: TypeId, registration: ComponentRegistration) {
+		if self.components.contains_key(&type_id) {
+			return;
+		}
+		if let Some(name) = registration.serialized_name() {
+			self.serialized_names.entry(name).or_insert(type_id);
+		}
+		if let Some(name) = registration.reflect_name() {
+			self.reflect_names.entry(name).or_insert(type_id);
+		}
+		self.components.insert(type_id, registration);
+	}
+
+	/// Mark the component type `T` as cloneable.
+	pub fn register_cloneable_component<T: Clone + 'static>(&mut self) {
+		self.component_entry::<T>().clone = Some(clone_boxed::<T>);
+	}
+
+	/// Include the component type `T` in world state hashes, ordered among the other components by
+	/// `name`.
+	pub fn register_hashable_component<T: Hash + 'static>(&mut self, name: &'static str) {
+		self.component_entry::<T>().hash = Some(HashRegistration { name, hash: hash_boxed::<T> });
+	}
+
+	/// Make the component type `T` available to data files under `name`.
+	pub fn register_serializable_component<T: Serialize + DeserializeOwned + 'static>(&mut self, name: &'static str) {
+		self.component_entry::<T>().serde = Some(SerdeRegistration {
+			name,
+			serialize: serialize_boxed::<T>,
+			deserialize: deserialize_boxed::<T>,
+		});
+		self.serialized_names.insert(name, TypeId::of::<T>());
+	}
+
+	/// Rewrite the entity references held by components of type `T` when they are loaded from a
+	/// scene.
+	pub fn register_map_entities<T: MapEntities + 'static>(&mut self) {
+		self.component_entry::<T>().map_entities = Some(map_entities_boxed::<T>);
+	}
+
+	/// Make the fields of the component type `T` accessible by name, with the component known as
+	/// `name`.
+	pub fn register_reflect_component<T: Reflect>(&mut self, name: &'static str) {
+		self.component_entry::<T>().reflect = Some(ReflectRegistration {
+			name,
+			reflect_mut: reflect_mut_boxed::<T>,
+		});
+		self.reflect_names.insert(name, TypeId::of::<T>());
+	}
+
+	/// Mark the resource type `T` as cloneable.
+	pub fn register_cloneable_resource<T: Clone + 'static>(&mut self) {
+		self.resources.insert(
+			TypeId::of::<T>(),
+			ResourceRegistration {
+				name: std::any::type_name::<T>(),
+				clone: clone_boxed::<T>,
+			},
+		);
+	}
+
+	pub fn component(&self, type_id: &TypeId) -> Option<&ComponentRegistration> {
+		self.components.get(type_id)
+	}
+
+	/// Look up a component type by the name it was registered under with
+	/// `register_serializable_component`.
+	pub fn component_by_name(&self, name: &str) -> Option<TypeId> {
+		self.serialized_names.get(name).copied()
+	}
+
+	/// Look up a component type by the name it was registered under with
+	/// `register_reflect_component`.
+	pub fn component_by_reflect_name(&self, name: &str) -> Option<TypeId> {
+		self.reflect_names.get(name).copied()
+	}
+
+	pub fn resource(&self, type_id: &TypeId) -> Option<&ResourceRegistration> {
+		self.resources.get(type_id)
+	}
+
+	pub fn resource_types(&self) -> impl Iterator<Item = &TypeId> {
+		self.resources.keys()
+	}
+
+	/// Clone a type-erased component, if its type was registered as cloneable.
+	pub fn clone_component(&self, type_id: &TypeId, component: &Component) -> Option<Component> {
+		self.components
+			.get(type_id)
+			.and_then(|registration| registration.clone)
+			.map(|clone| clone(component.as_ref()))
+	}
+
+	/// Feed a type-erased component into `hasher`, if its type was registered as hashable.
+	pub fn hash_component(&self, type_id: &TypeId, component: &Component, hasher: &mut StableHasher) {
+		if let Some(hash) = self.components.get(type_id).and_then(|registration| registration.hash) {
+			(hash.hash)(component.as_ref(), hasher);
+		}
+	}
+
+	/// Serialize a type-erased component, if its type was registered as serializable.
+	pub fn serialize_component(&self, type_id: &TypeId, component: &Component) -> Option<Result<Value>> {
+		self.components
+			.get(type_id)
+			.and_then(|registration| registration.serde)
+			.map(|serde| (serde.serialize)(component.as_ref()))
+	}
+
+	/// Deserialize a component of the given type, if it was registered as serializable.
+	pub fn deserialize_component(&self, type_id: &TypeId, value: &Value) -> Option<Result<Component>> {
+		self.components
+			.get(type_id)
+			.and_then(|registration| registration.serde)
+			.map(|serde| (serde.deserialize)(value))
+	}
+
+	/// Rewrite the entity references of a type-erased component, if its type implements
+	/// `MapEntities` and was registered with `register_map_entities`.
+	pub fn map_component_entities(&self, type_id: &TypeId, component: &mut Component, entity_map: &EntityMap) -> Result<()> {
+		self.components
+			.get(type_id)
+			.and_then(|registration| registration.map_entities)
+			.map_or(Ok(()), |map_entities| map_entities(component.as_mut(), entity_map))
+	}
+
+	/// Clone a type-erased resource, if its type was registered as cloneable.
+	pub fn clone_resource(&self, type_id: &TypeId, resource: &dyn Any) -> Option<Box<dyn Any>> {
+		self.resources.get(type_id).map(|registration| (registration.clone)(resource))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+	struct Position(f32, f32);
+
+	#[test]
+	fn clone_registered_component() {
+		let mut registry = TypeRegistry::new();
+		let component: Component = Box::new(Position(1.0, 2.0));
+		assert!(registry.clone_component(&TypeId::of::<Position>(), &component).is_none());
+
+		registry.register_cloneable_component::<Position>();
+		let cloned = registry.clone_component(&TypeId::of::<Position>(), &component).unwrap();
+		assert_eq!(cloned.downcast_ref::<Position>(), Some(&Position(1.0, 2.0)));
+		assert_eq!(registry.component(&TypeId::of::<Position>()).unwrap().name(), std::any::type_name::<Position>());
+	}
+
+	#[test]
+	fn serialize_registered_component() -> Result<()> {
+		let mut registry = TypeRegistry::new();
+		registry.register_serializable_component::<Position>("Position");
+		let type_id = registry.component_by_name("Position").unwrap();
+
+		let component: Component = Box::new(Position(1.0, 2.0));
+		let value = registry.serialize_component(&type_id, &component).unwrap()?;
+		assert_eq!(value, serde_json::json!([1.0, 2.0]));
+
+		let component = registry.deserialize_component(&type_id, &value).unwrap()?;
+		assert_eq!(component.downcast_ref::<Position>(), Some(&Position(1.0, 2.0)));
+
+		Ok(())
+	}
+}
@@ -6,6 +6,8 @@ use std::{
 #[derive(Default)]
 pub struct ResourceMap {
 	data: HashMap<TypeId, Box<dyn Any + 'static>>,
+	changes: HashMap<TypeId, u64>,
+	change_tick: u64,
 }
 
 impl ResourceMap {
@@ -21,19 +23,58 @@ impl ResourceMap {
 	}
 
 	/// Retrieve a mutable reference to the value stored in the map for the type `T`, if it exists.
+	/// Counts as a change of the value, whether or not it is modified.
 	pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+		if self.data.contains_key(&TypeId::of::<T>()) {
+			self.mark_changed(TypeId::of::<T>());
+		}
+		self.data.get_mut(&TypeId::of::<T>()).and_then(|any| any.downcast_mut())
+	}
+
+	/// Like `get_mut`, but does not count as a change. For bookkeeping such as recording
+	/// diagnostics, which `resource_changed` should not react to.
+	pub(crate) fn get_mut_untracked<T: 'static>(&mut self) -> Option<&mut T> {
 		self.data.get_mut(&TypeId::of::<T>()).and_then(|any| any.downcast_mut())
 	}
 
 	/// Set the value contained in the map for the type `T`.
 	/// This will override any previous value stored.
 	pub fn insert<T: 'static>(&mut self, value: T) {
-		self.data.insert(TypeId::of::<T>(), Box::new(value) as _);
+		self.insert_by_id(TypeId::of::<T>(), Box::new(value));
 	}
 
 	/// Remove the value for the type `T` if it existed.
 	pub fn remove<T: 'static>(&mut self) {
-		self.data.remove(&TypeId::of::<T>());
+		self.remove_by_id(&TypeId::of::<T>());
+	}
+
+	/// A counter value that increases every time the value for the type `T` is inserted or
+	/// borrowed mutably, if it exists. Compare it with an earlier reading to detect changes.
+	pub fn change_tick<T: 'static>(&self) -> Option<u64> {
+		self.changes.get(&TypeId::of::<T>()).copied()
+	}
+
+	fn mark_changed(&mut self, type_id: TypeId) {
+		self.change_tick += 1;
+		self.changes.insert(type_id, self.change_tick);
+	}
+
+	/// Retrieve the type-erased value stored for the given type id, if it exists.
+	pub fn get_by_id(&self, type_id: &TypeId) -> Option<&dyn Any> {
+		self.data.get(type_id).map(|any| any.as_ref())
+	}
+
+	/// Set the type-erased value stored for the given type id.
+	/// The value must be of the type identified by `type_id`.
+	pub fn insert_by_id(&mut self, type_id: TypeId, value: Box<dyn Any>) {
+		self.data.insert(type_id, value);
+		self.mark_changed(type_id);
+	}
+
+	/// Remove the value for the given type id if it existed.
+	pub fn remove_by_id(&mut self, type_id: &TypeId) {
+		self.data.remove(type_id);
+		self.changes.remove(type_id);
 	}
 }
 
@@ -63,4 +104,22 @@ mod tests {
 		resources.remove::<Viewport>();
 		assert_eq!(resources.get::<Viewport>(), None);
 	}
+
+	#[test]
+	fn change_ticks() {
+		let mut resources = ResourceMap::new();
+		assert_eq!(resources.change_tick::<Viewport>(), None);
+
+		resources.insert(Viewport::default());
+		let inserted = resources.change_tick::<Viewport>().unwrap();
+
+		resources.get::<Viewport>();
+		assert_eq!(resources.change_tick::<Viewport>(), Some(inserted));
+
+		resources.get_mut::<Viewport>();
+		assert!(resources.change_tick::<Viewport>().unwrap() > inserted);
+
+		resources.remove::<Viewport>();
+		assert_eq!(resources.change_tick::<Viewport>(), None);
+	}
 }
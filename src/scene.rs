@@ -0,0 +1,268 @@
+use crate::{
+	error::Result,
+	hierarchy::{Children, Parent},
+	prefab::error::UnknownComponentError,
+	world::{Component, Entity, World},
+};
+use error::UnmappedEntityError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+	any::TypeId,
+	collections::{BTreeMap, HashMap},
+};
+
+pub mod error {
+	use crate::world::Entity;
+
+	#[derive(Debug)]
+	pub struct UnmappedEntityError {
+		pub entity: Entity,
+	}
+
+	impl std::error::Error for UnmappedEntityError {}
+
+	impl std::fmt::Display for UnmappedEntityError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Entity {:?} is referenced but is not part of the scene.", self.entity)
+		}
+	}
+}
+
+/// The serializable components of a set of entities, keyed by the entity handles they had when
+/// the scene was saved.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scene {
+	pub entities: Vec<SceneEntity>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SceneEntity {
+	pub entity: Entity,
+	pub components: BTreeMap<String, Value>,
+}
+
+impl Scene {
+	pub fn from_json(json: &str) -> Result<Self> {
+		trace_span!("scene_from_json", bytes = json.len());
+		Ok(serde_json::from_str(json)?)
+	}
+
+	pub fn to_json(&self) -> Result<String> {
+		trace_span!("scene_to_json", entities = self.entities.len());
+		Ok(serde_json::to_string_pretty(self)?)
+	}
+}
+
+/// Maps the entities of a scene to the entities they were loaded as.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EntityMap {
+	entities: HashMap<Entity, Entity>,
+}
+
+impl EntityMap {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn insert(&mut self, from: Entity, to: Entity) {
+		self.entities.insert(from, to);
+	}
+
+	pub fn get(&self, entity: Entity) -> Option<Entity> {
+		self.entities.get(&entity).copied()
+	}
+
+	/// The entity `entity` was loaded as. Fails if `entity` was not part of the scene, since the
+	/// old handle could otherwise alias an unrelated entity of the world.
+	pub fn map(&self, entity: Entity) -> Result<Entity> {
+		Ok(self.get(entity).ok_or(UnmappedEntityError { entity })?)
+	}
+
+	pub fn len(&self) -> usize {
+		self.entities.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entities.is_empty()
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&Entity, &Entity)> {
+		self.entities.iter()
+	}
+}
+
+/// Implemented by components that reference other entities, so that those references can be
+/// rewritten when a scene is merged into a world.
+pub trait MapEntities {
+	fn map_entities(&mut self, entity_map: &EntityMap) -> Result<()>;
+}
+
+impl MapEntities for Parent {
+	fn map_entities(&mut self, entity_map: &EntityMap) -> Result<()> {
+		self.0 = entity_map.map(self.0)?;
+		Ok(())
+	}
+}
+
+impl MapEntities for Children {
+	fn map_entities(&mut self, entity_map: &EntityMap) -> Result<()> {
+		self.0.iter_mut().try_for_each(|child| {
+			*child = entity_map.map(*child)?;
+			Ok(())
+		})
+	}
+}
+
+impl World {
+	/// Capture the serializable components of every entity in the world.
+	pub fn save_scene(&self) -> Result<Scene> {
+		trace_span!("save_scene");
+		let entities = self
+			.allocator
+			.allocated_handles()
+			.into_iter()
+			.map(|entity| {
+				self.entity_to_prefab(entity).map(|prefab| SceneEntity {
+					entity,
+					components: prefab.components,
+				})
+			})
+			.collect::<Result<Vec<_>>>()?;
+		Ok(Scene { entities })
+	}
+
+	/// Merge `scene` into the world.
+	///
+	/// Every scene entity is given a freshly allocated entity, so existing entities are never
+	/// overwritten. Components registered with `register_map_entities` have their entity
+	/// references rewritten to the new entities; a reference to an entity outside the scene is an
+	/// error. If any component fails to load, every entity spawned for the scene is removed.
+	pub fn load_scene(&mut self, scene: &Scene) -> Result<EntityMap> {
+		trace_span!("load_scene", entities = scene.entities.len());
+		let mut entity_map = EntityMap::new();
+		for scene_entity in scene.entities.iter() {
+			let entity = self.create_entity();
+			entity_map.insert(scene_entity.entity, entity);
+		}
+
+		let result = self.insert_scene_components(scene, &entity_map);
+		if let Err(error) = result {
+			let spawned = entity_map.iter().map(|(_, entity)| *entity).collect::<Vec<_>>();
+			self.remove_entities(&spawned);
+			return Err(error);
+		}
+
+		Ok(entity_map)
+	}
+
+	fn insert_scene_components(&mut self, scene: &Scene, entity_map: &EntityMap) -> Result<()> {
+		let mut components: Vec<(Entity, TypeId, Component)> = Vec::new();
+		for scene_entity in scene.entities.iter() {
+			let entity = entity_map.map(scene_entity.entity)?;
+			for (name, value) in scene_entity.components.iter() {
+				let type_id = self.registry.component_by_name(name).ok_or_else(|| UnknownComponentError { name: name.clone() })?;
+				let mut component = self
+					.registry
+					.deserialize_component(&type_id, value)
+					.ok_or_else(|| UnknownComponentError { name: name.clone() })??;
+				self.registry.map_component_entities(&type_id, &mut component, entity_map)?;
+				components.push((entity, type_id, component));
+			}
+		}
+
+		components
+			.into_iter()
+			.try_for_each(|(entity, type_id, component)| self.assign_component_by_id(entity, type_id, Some(component)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+	struct Position {
+		x: f32,
+		y: f32,
+	}
+
+	#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+	struct Target(Entity);
+
+	impl MapEntities for Target {
+		fn map_entities(&mut self, entity_map: &EntityMap) -> Result<()> {
+			self.0 = entity_map.map(self.0)?;
+			Ok(())
+		}
+	}
+
+	fn world() -> World {
+		let mut world = World::new();
+		world.register_serializable_component::<Position>("Position");
+		world.register_serializable_component::<Target>("Target");
+		world.register_map_entities::<Target>();
+		world.register_serializable_component::<Parent>("Parent");
+		world.register_map_entities::<Parent>();
+		world
+	}
+
+	#[test]
+	fn load_scene_remaps_entities() -> Result<()> {
+		let mut source = world();
+		let hunter = source.create_entity();
+		let prey = source.create_entity();
+		source.add_component(hunter, Position { x: 1.0, y: 1.0 })?;
+		source.add_component(hunter, Target(prey))?;
+		source.add_component(prey, Parent(hunter))?;
+		let scene = Scene::from_json(&source.save_scene()?.to_json()?)?;
+
+		let mut world = world();
+		let existing = world.create_entities(2);
+		world.add_component(existing[0], Position::default())?;
+
+		let entity_map = world.load_scene(&scene)?;
+		let (hunter, prey) = (entity_map.get(hunter).unwrap(), entity_map.get(prey).unwrap());
+
+		assert!(!existing.contains(&hunter) && !existing.contains(&prey));
+		assert_eq!(world.get_component::<Position>(existing[0]).as_deref(), Some(&Position::default()));
+		assert_eq!(world.get_component::<Position>(hunter).as_deref(), Some(&Position { x: 1.0, y: 1.0 }));
+		assert_eq!(world.get_component::<Target>(hunter).as_deref(), Some(&Target(prey)));
+		assert_eq!(world.get_component::<Parent>(prey).as_deref(), Some(&Parent(hunter)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn load_scene_with_unknown_component() -> Result<()> {
+		let mut world = world();
+		let scene = Scene::from_json(
+			r#"{ "entities": [
+				{ "entity": { "index": 0, "generation": 0 }, "components": { "Position": { "x": 0.0, "y": 0.0 } } },
+				{ "entity": { "index": 1, "generation": 0 }, "components": { "Mana": 10 } }
+			] }"#,
+		)?;
+
+		assert!(world.load_scene(&scene).is_err());
+		assert!(world.allocator.allocated_handles().is_empty());
+
+		Ok(())
+	}
+
+	#[test]
+	fn load_scene_with_dangling_reference() -> Result<()> {
+		let mut world = world();
+		let bystander = world.create_entity();
+		let scene = Scene::from_json(&format!(
+			r#"{{ "entities": [
+				{{ "entity": {{ "index": 5, "generation": 0 }}, "components": {{ "Target": {} }} }}
+			] }}"#,
+			serde_json::to_string(&bystander)?,
+		))?;
+
+		assert!(world.load_scene(&scene).is_err());
+		assert_eq!(world.allocator.allocated_handles(), vec![bystander]);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,655 @@
+use self::error::{SystemDisabledError, SystemError, SystemErrors, SystemPanicError};
+use crate::{
+	diagnostics::Diagnostics,
+	error::Result,
+	world::{Entity, World},
+};
+use std::{
+	panic::{catch_unwind, AssertUnwindSafe},
+	rc::Rc,
+	sync::atomic::{AtomicU64, Ordering},
+	time::Instant,
+};
+
+pub mod error {
+	use crate::world::Entity;
+
+	/// An error returned by a system, with the entity it was processing if it is known.
+	#[derive(Debug)]
+	pub struct SystemError {
+		pub system: String,
+		pub entity: Option<Entity>,
+		pub error: Box<dyn std::error::Error>,
+	}
+
+	impl std::error::Error for SystemError {}
+
+	impl std::fmt::Display for SystemError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			match self.entity {
+				Some(entity) => write!(f, "System '{}' failed for entity {:?}: {}", self.system, entity, self.error),
+				None => write!(f, "System '{}' failed: {}", self.system, self.error),
+			}
+		}
+	}
+
+	/// A panic caught while running a system, with the panic message.
+	#[derive(Debug)]
+	pub struct SystemPanicError(pub String);
+
+	impl std::error::Error for SystemPanicError {}
+
+	impl std::fmt::Display for SystemPanicError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "panicked: {}", self.0)
+		}
+	}
+
+	/// Reported to the error handler when `ErrorPolicy::DisableAfter` disables a system.
+	#[derive(Debug)]
+	pub struct SystemDisabledError {
+		pub failures: u32,
+	}
+
+	impl std::error::Error for SystemDisabledError {}
+
+	impl std::fmt::Display for SystemDisabledError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "disabled after {} failed runs", self.failures)
+		}
+	}
+
+	/// The errors collected during one run of a schedule by systems using `ErrorPolicy::Collect`.
+	#[derive(Debug)]
+	pub struct SystemErrors(pub Vec<SystemError>);
+
+	impl std::error::Error for SystemErrors {}
+
+	impl std::fmt::Display for SystemErrors {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			let messages = self.0.iter().map(ToString::to_string).collect::<Vec<_>>();
+			write!(f, "{} system errors: {}", self.0.len(), messages.join("; "))
+		}
+	}
+}
+
+/// What a schedule does when a system fails.
+///
+/// Systems generated by `system!` report which entity failed. Under every policy but
+/// `StopOnFirst` they also carry on with the remaining entities.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+	/// Abort the schedule with the first error as a `SystemError`.
+	#[default]
+	StopOnFirst,
+
+	/// Keep running, and return every error of the run together as `SystemErrors` once all systems
+	/// have run.
+	Collect,
+
+	/// Pass every error to the schedule's error handler and keep running.
+	LogAndSkip,
+
+	/// Pass every error to the schedule's error handler and keep running, but stop running the
+	/// system once this many of its runs have failed, until it is re-enabled with
+	/// `Schedule::enable_system`. Disabling the system is reported as a `SystemDisabledError`.
+	DisableAfter(u32),
+}
+
+/// Receives the errors of systems running under `LogAndSkip` and `DisableAfter`, see
+/// `Schedule::set_error_handler`.
+pub type ErrorHandler = Rc<dyn Fn(&SystemError)>;
+
+/// The default error handler. Emits an error event with the `tracing` feature, and does nothing
+/// otherwise. Use `Schedule::set_error_handler` to report errors without the feature.
+#[cfg(feature = "tracing")]
+pub fn log_error(error: &SystemError) {
+	tracing::error!(system = error.system.as_str(), "{error}");
+}
+
+#[cfg(not(feature = "tracing"))]
+pub const fn log_error(_: &SystemError) {}
+
+/// A unit of logic run against a `World` by a `Schedule`.
+///
+/// Implemented for every `FnMut(&World) -> Result<()>`, which includes the functions generated by
+/// `system!` without extra arguments. Systems only get shared access to the world; structural
+/// changes are queued through `World::commands` and applied when the schedule flushes the world.
+pub trait System {
+	fn name(&self) -> &str {
+		std::any::type_name::<Self>()
+	}
+
+	/// The policy overriding the schedule's for this system, see `SystemExt::on_error`.
+	fn error_policy(&self) -> Option<ErrorPolicy> {
+		None
+	}
+
+	fn run(&mut self, world: &World) -> Result<()>;
+}
+
+impl<F: FnMut(&World) -> Result<()>> System for F {
+	fn run(&mut self, world: &World) -> Result<()> {
+		self(world)
+	}
+}
+
+/// A system with its own error policy, see `SystemExt::on_error`.
+pub struct OnError<S> {
+	pub(crate) system: S,
+	pub(crate) policy: ErrorPolicy,
+}
+
+impl<S: System> System for OnError<S> {
+	fn name(&self) -> &str {
+		self.system.name()
+	}
+
+	fn error_policy(&self) -> Option<ErrorPolicy> {
+		Some(self.policy)
+	}
+
+	fn run(&mut self, world: &World) -> Result<()> {
+		self.system.run(world)
+	}
+}
+
+/// A system that takes the `World` mutably, for work such as bulk despawns or level streaming that
+/// cannot be expressed through shared access.
+///
+/// Implemented for every `FnMut(&mut World) -> Result<()>`.
+pub trait ExclusiveSystem {
+	fn name(&self) -> &str {
+		std::any::type_name::<Self>()
+	}
+
+	fn run(&mut self, world: &mut World) -> Result<()>;
+}
+
+impl<F: FnMut(&mut World) -> Result<()>> ExclusiveSystem for F {
+	fn run(&mut self, world: &mut World) -> Result<()> {
+		self(world)
+	}
+}
+
+/// Identifies a system added to a schedule, unique across every schedule of the process. Systems
+/// created from the same function share a name but not an id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SystemId(pub u64);
+
+impl SystemId {
+	fn next() -> Self {
+		static NEXT: AtomicU64 = AtomicU64::new(0);
+		Self(NEXT.fetch_add(1, Ordering::Relaxed))
+	}
+}
+
+enum SystemKind {
+	Shared(Box<dyn System>),
+	Exclusive(Box<dyn ExclusiveSystem>),
+}
+
+struct ScheduledSystem {
+	id: SystemId,
+	kind: SystemKind,
+	failures: u32,
+	disabled: bool,
+}
+
+impl ScheduledSystem {
+	fn new(kind: SystemKind) -> Self {
+		Self {
+			id: SystemId::next(),
+			kind,
+			failures: 0,
+			disabled: false,
+		}
+	}
+
+	fn name(&self) -> &str {
+		match &self.kind {
+			SystemKind::Shared(system) => system.name(),
+			SystemKind::Exclusive(system) => system.name(),
+		}
+	}
+
+	fn error_policy(&self) -> Option<ErrorPolicy> {
+		match &self.kind {
+			SystemKind::Shared(system) => system.error_policy(),
+			SystemKind::Exclusive(_) => None,
+		}
+	}
+
+	fn run(&mut self, world: &mut World) -> Result<()> {
+		match &mut self.kind {
+			SystemKind::Shared(system) => system.run(world),
+			SystemKind::Exclusive(system) => {
+				world.flush()?;
+				system.run(world)?;
+				world.flush()
+			},
+		}
+	}
+}
+
+/// An ordered list of systems.
+pub struct Schedule {
+	systems: Vec<ScheduledSystem>,
+	error_policy: ErrorPolicy,
+	error_handler: ErrorHandler,
+	catch_panics: bool,
+}
+
+impl Default for Schedule {
+	fn default() -> Self {
+		Self {
+			systems: Vec::new(),
+			error_policy: ErrorPolicy::default(),
+			error_handler: Rc::new(log_error),
+			catch_panics: false,
+		}
+	}
+}
+
+impl Schedule {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Append a system, to run after the systems added before it. Returns its id, which
+	/// identifies it in `disabled_systems`, `enable_system` and `Diagnostics`.
+	pub fn add_system(&mut self, system: impl System + 'static) -> SystemId {
+		self.push_system(SystemKind::Shared(Box::new(system)))
+	}
+
+	/// Append an exclusive system. It acts as a sync point: the commands queued by the systems
+	/// before it are flushed before it runs, and the ones it queues are flushed right after.
+	pub fn add_exclusive_system(&mut self, system: impl ExclusiveSystem + 'static) -> SystemId {
+		self.push_system(SystemKind::Exclusive(Box::new(system)))
+	}
+
+	fn push_system(&mut self, kind: SystemKind) -> SystemId {
+		let system = ScheduledSystem::new(kind);
+		let id = system.id;
+		self.systems.push(system);
+		id
+	}
+
+	/// Set the policy for systems that do not have their own.
+	pub const fn set_error_policy(&mut self, error_policy: ErrorPolicy) -> &mut Self {
+		self.error_policy = error_policy;
+		self
+	}
+
+	pub const fn error_policy(&self) -> ErrorPolicy {
+		self.error_policy
+	}
+
+	/// Replace `log_error` as the receiver of the errors that `LogAndSkip` and `DisableAfter` keep
+	/// running after.
+	pub fn set_error_handler(&mut self, error_handler: impl Fn(&SystemError) + 'static) -> &mut Self {
+		self.error_handler = Rc::new(error_handler);
+		self
+	}
+
+	pub(crate) fn set_shared_error_handler(&mut self, error_handler: ErrorHandler) {
+		self.error_handler = error_handler;
+	}
+
+	/// Run every system under `catch_unwind`. A panicking system fails with a `SystemPanicError`,
+	/// handled by its error policy, and is quarantined: it does not run again until it is
+	/// re-enabled with `enable_system`. Borrows the system held are released while unwinding.
+	pub const fn set_catch_panics(&mut self, catch_panics: bool) -> &mut Self {
+		self.catch_panics = catch_panics;
+		self
+	}
+
+	pub const fn catches_panics(&self) -> bool {
+		self.catch_panics
+	}
+
+	pub const fn len(&self) -> usize {
+		self.systems.len()
+	}
+
+	pub const fn is_empty(&self) -> bool {
+		self.systems.is_empty()
+	}
+
+	/// The names of the systems, in the order they run.
+	pub fn system_names(&self) -> Vec<&str> {
+		self.systems.iter().map(ScheduledSystem::name).collect()
+	}
+
+	/// The ids of the systems, in the order they run.
+	pub fn system_ids(&self) -> Vec<SystemId> {
+		self.systems.iter().map(|system| system.id).collect()
+	}
+
+	/// The ids of the systems that were disabled by their error policy or quarantined after a
+	/// panic.
+	pub fn disabled_systems(&self) -> Vec<SystemId> {
+		self.systems.iter().filter(|system| system.disabled).map(|system| system.id).collect()
+	}
+
+	/// Run the system `id` again and reset its failure count. Returns whether it was disabled.
+	pub fn enable_system(&mut self, id: SystemId) -> bool {
+		self.systems.iter_mut().find(|system| system.id == id).is_some_and(|system| {
+			system.failures = 0;
+			std::mem::replace(&mut system.disabled, false)
+		})
+	}
+
+	/// Run every enabled system in order, then flush the commands they queued. Commands are also
+	/// flushed around every exclusive system.
+	///
+	/// Failures are handled by the error policy of each system. Under `StopOnFirst` the schedule
+	/// discards the commands and entity reservations of the failing system, flushes the ones queued
+	/// by the systems before it and stops.
+	pub fn run(&mut self, world: &mut World) -> Result<()> {
+		trace_span!("schedule", systems = self.systems.len());
+		let mut collected = Vec::new();
+		for system in self.systems.iter_mut().filter(|system| !system.disabled) {
+			let name = system.name().to_string();
+			trace_span!("system", name = name.as_str());
+			let policy = system.error_policy().unwrap_or(self.error_policy);
+			let (queued, reserved) = (world.commands.get_mut().len(), world.allocator.reserved_count());
+			world.system_run.replace(SystemRun {
+				collect: policy != ErrorPolicy::StopOnFirst,
+				..SystemRun::default()
+			});
+			let start = Instant::now();
+			let result = if self.catch_panics {
+				catch_unwind(AssertUnwindSafe(|| system.run(world))).unwrap_or_else(|payload| {
+					system.disabled = true;
+					let message = payload
+						.downcast_ref::<&str>()
+						.map(ToString::to_string)
+						.or_else(|| payload.downcast_ref::<String>().cloned())
+						.unwrap_or_default();
+					Err(Box::new(SystemPanicError(message)))
+				})
+			} else {
+				system.run(world)
+			};
+			let elapsed = start.elapsed();
+			let SystemRun {
+				failed,
+				errors,
+				entities,
+				skipped,
+				..
+			} = world.system_run.take();
+
+			if let Some(diagnostics) = world.resources().borrow_mut().get_mut_untracked::<Diagnostics>().filter(|_| !skipped) {
+				diagnostics.record_system(system.id, &name, elapsed, entities);
+			}
+			let mut errors = errors
+				.into_iter()
+				.map(|(entity, error)| SystemError {
+					system: name.clone(),
+					entity: Some(entity),
+					error,
+				})
+				.collect::<Vec<_>>();
+			if let Err(error) = result {
+				errors.push(SystemError {
+					system: name,
+					entity: failed,
+					error,
+				});
+			}
+			if errors.is_empty() {
+				continue;
+			}
+
+			match policy {
+				ErrorPolicy::StopOnFirst => {
+					world.discard_commands_since(queued, reserved);
+					world.flush()?;
+					return Err(Box::new(errors.remove(0)));
+				},
+				ErrorPolicy::Collect => collected.extend(errors),
+				ErrorPolicy::LogAndSkip => errors.iter().for_each(|error| (self.error_handler)(error)),
+				ErrorPolicy::DisableAfter(failures) => {
+					errors.iter().for_each(|error| (self.error_handler)(error));
+					system.failures += 1;
+					if system.failures >= failures {
+						system.disabled = true;
+						(self.error_handler)(&SystemError {
+							system: system.name().to_string(),
+							entity: None,
+							error: Box::new(SystemDisabledError { failures: system.failures }),
+						});
+					}
+				},
+			}
+		}
+		world.flush()?;
+		if collected.is_empty() { Ok(()) } else { Err(Box::new(SystemErrors(collected))) }
+	}
+}
+
+/// What the systems `system!` generates report about the entities they process.
+#[derive(Default)]
+pub(crate) struct SystemRun {
+	collect: bool,
+	skipped: bool,
+	entities: usize,
+	failed: Option<Entity>,
+	errors: Vec<(Entity, Box<dyn std::error::Error>)>,
+}
+
+impl World {
+	/// Called by `RunIf` when its condition is false, so the run is not recorded in `Diagnostics`.
+	pub(crate) fn skip_system_run(&self) {
+		self.system_run.borrow_mut().skipped = true;
+	}
+
+	/// Called by `system!` to process the entity at `index`. If processing fails, returns the error
+	/// when the running system should stop, or records it and returns `Ok` when the system should
+	/// carry on with the next entity.
+	#[doc(hidden)]
+	pub fn run_for_entity<E: Into<Box<dyn std::error::Error>>>(&self, index: usize, body: impl FnOnce() -> Result<(), E>) -> Result<(), E> {
+		self.system_run.borrow_mut().entities += 1;
+		let error = match body() {
+			Ok(()) => return Ok(()),
+			Err(error) => error,
+		};
+		let Some(entity) = self.allocator.handle_at(index) else {
+			return Err(error);
+		};
+		let mut system_run = self.system_run.borrow_mut();
+		if !system_run.collect {
+			system_run.failed = Some(entity);
+			return Err(error);
+		}
+		system_run.errors.push((entity, error.into()));
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{condition::SystemExt, system};
+
+	#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+	struct Counter(u32);
+
+	fn count(world: &World) -> Result<()> {
+		world.resources().borrow_mut().get_mut::<Counter>().unwrap().0 += 1;
+		Ok(())
+	}
+
+	#[test]
+	fn systems_run_in_order() -> Result<()> {
+		let mut world = World::new();
+		world.resources().borrow_mut().insert(Counter::default());
+
+		let mut schedule = Schedule::new();
+		schedule.add_system(count);
+		schedule.add_system(|world: &World| {
+			let entity = world.reserve_entity();
+			let counter = *world.resources().borrow().get::<Counter>().unwrap();
+			world.commands().add_component(entity, counter);
+			Ok(())
+		});
+		assert_eq!(schedule.len(), 2);
+
+		schedule.run(&mut world)?;
+
+		let entity = world.allocator().allocated_handles()[0];
+		assert_eq!(world.get_component::<Counter>(entity).as_deref(), Some(&Counter(1)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn schedule_stops_at_failing_system() {
+		let mut world = World::new();
+		world.resources().borrow_mut().insert(Counter::default());
+
+		let mut schedule = Schedule::new();
+		schedule.add_system(|world: &World| {
+			let entity = world.reserve_entity();
+			world.commands().add_component(entity, Counter(5));
+			Ok(())
+		});
+		schedule.add_system(|world: &World| {
+			let entity = world.reserve_entity();
+			world.commands().add_component(entity, Counter(6));
+			Err("failed".into())
+		});
+		schedule.add_system(count);
+
+		assert!(schedule.run(&mut world).is_err());
+		let entities = world.allocator().allocated_handles();
+		assert_eq!(entities.len(), 1);
+		assert_eq!(world.get_component::<Counter>(entities[0]).as_deref(), Some(&Counter(5)));
+		assert_eq!(world.resources().borrow().get::<Counter>(), Some(&Counter(0)));
+	}
+
+	#[test]
+	fn exclusive_systems_are_sync_points() -> Result<()> {
+		let mut world = World::new();
+		world.resources().borrow_mut().insert(Counter::default());
+
+		let mut schedule = Schedule::new();
+		schedule.add_system(|world: &World| {
+			let entity = world.reserve_entity();
+			world.commands().add_component(entity, Counter(1));
+			Ok(())
+		});
+		schedule.add_exclusive_system(|world: &mut World| {
+			let entities = world.allocator().allocated_handles();
+			world.resources().borrow_mut().get_mut::<Counter>().unwrap().0 = entities.len() as u32;
+			world.remove_entities(&entities);
+			Ok(())
+		});
+		schedule.add_system(count);
+		assert_eq!(schedule.len(), 3);
+
+		schedule.run(&mut world)?;
+		assert_eq!(world.resources().borrow().get::<Counter>(), Some(&Counter(2)));
+		assert!(world.allocator().allocated_handles().is_empty());
+
+		Ok(())
+	}
+
+	#[test]
+	fn panicking_systems_are_quarantined() -> Result<()> {
+		let mut world = World::new();
+		world.resources().borrow_mut().insert(Counter::default());
+		let mut schedule = Schedule::new();
+		schedule.set_catch_panics(true);
+		let guard = schedule.add_system(|world: &World| {
+			let resources = world.resources().borrow_mut();
+			assert!(resources.get::<Counter>().unwrap().0 < 1, "counter overflowed");
+			Ok(())
+		});
+		schedule.add_system(count);
+
+		schedule.run(&mut world)?;
+		let error = schedule.run(&mut world).unwrap_err().downcast::<SystemError>().unwrap();
+		assert!(error.to_string().ends_with("panicked: counter overflowed"));
+		assert_eq!(schedule.disabled_systems(), vec![guard]);
+
+		schedule.run(&mut world)?;
+		assert_eq!(world.resources().borrow().get::<Counter>(), Some(&Counter(2)));
+
+		Ok(())
+	}
+
+	#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+	struct Health(i32);
+
+	system!(heal_system, [_resources, entity], (), (health: Health) -> Result<()> {
+		if health.0 <= 0 {
+			return Err(format!("entity {entity} is dead").into());
+		}
+		health.0 += 1;
+		Ok(())
+	});
+
+	fn world_with_health(values: &[i32]) -> Result<(World, Vec<Entity>)> {
+		let mut world = World::new();
+		let entities = world.create_entities(values.len());
+		for (entity, value) in entities.iter().zip(values) {
+			world.add_component(*entity, Health(*value))?;
+		}
+		Ok((world, entities))
+	}
+
+	#[test]
+	fn collected_errors_name_system_and_entity() -> Result<()> {
+		let (mut world, entities) = world_with_health(&[1, 0, 2, -1])?;
+
+		let mut schedule = Schedule::new();
+		schedule.add_system(heal_system);
+		let error = schedule.run(&mut world).unwrap_err().downcast::<SystemError>().unwrap();
+		assert_eq!(error.entity, Some(entities[1]));
+		assert_eq!(world.get_component::<Health>(entities[2]).as_deref(), Some(&Health(2)));
+
+		schedule.set_error_policy(ErrorPolicy::Collect);
+		let errors = schedule.run(&mut world).unwrap_err().downcast::<SystemErrors>().unwrap();
+		assert_eq!(
+			errors.0.iter().map(|error| error.entity).collect::<Vec<_>>(),
+			vec![Some(entities[1]), Some(entities[3])]
+		);
+		assert!(errors.0.iter().all(|error| error.system.ends_with("heal_system")));
+		assert_eq!(world.get_component::<Health>(entities[2]).as_deref(), Some(&Health(3)));
+
+		Ok(())
+	}
+
+	#[test]
+	fn systems_are_disabled_after_failures() -> Result<()> {
+		let (mut world, entities) = world_with_health(&[1, 0])?;
+		let reported = Rc::new(std::cell::RefCell::new(Vec::new()));
+		let mut schedule = Schedule::new();
+		let healing = schedule.add_system(heal_system.on_error(ErrorPolicy::DisableAfter(2)));
+		let unrelated = schedule.add_system(heal_system.on_error(ErrorPolicy::LogAndSkip));
+		schedule.set_error_handler({
+			let reported = reported.clone();
+			move |error| reported.borrow_mut().push((error.entity, error.error.to_string()))
+		});
+
+		schedule.run(&mut world)?;
+		schedule.run(&mut world)?;
+		schedule.run(&mut world)?;
+		assert_eq!(world.get_component::<Health>(entities[0]).as_deref(), Some(&Health(6)));
+		assert_eq!(schedule.disabled_systems(), vec![healing]);
+		assert_eq!(reported.borrow().len(), 6);
+		assert_eq!(reported.borrow()[0].0, Some(entities[1]));
+		assert_eq!(reported.borrow()[3], (None, "disabled after 2 failed runs".to_string()));
+
+		assert!(!schedule.enable_system(unrelated));
+		assert!(schedule.enable_system(healing));
+		assert!(schedule.disabled_systems().is_empty());
+		schedule.run(&mut world)?;
+		assert_eq!(world.get_component::<Health>(entities[0]).as_deref(), Some(&Health(8)));
+
+		Ok(())
+	}
+}
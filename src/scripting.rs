@@ -0,0 +1,347 @@
+use self::error::{ScriptError, ScriptNotFoundError};
+use crate::{
+	error::Result,
+	schedule::System,
+	world::{Entity, World},
+};
+use rhai::{AST, CallFnOptions, Dynamic, Engine, Map, Scope};
+
+pub mod error {
+	#[derive(Debug)]
+	pub struct ScriptNotFoundError {
+		pub name: String,
+	}
+
+	impl std::error::Error for ScriptNotFoundError {}
+
+	impl std::fmt::Display for ScriptNotFoundError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Script system '{}' does not exist.", self.name)
+		}
+	}
+
+	/// A script system that failed, with the error that stopped it.
+	#[derive(Debug)]
+	pub struct ScriptError {
+		pub name: String,
+		pub message: String,
+	}
+
+	impl std::error::Error for ScriptError {}
+
+	impl std::fmt::Display for ScriptError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Script system '{}' failed: {}", self.name, self.message)
+		}
+	}
+}
+
+/// A system written in Rhai.
+///
+/// The script declares the components it needs with a `query` function returning their
+/// reflection names, and defines a `run` function that is called once per enabled entity holding
+/// all of them. Inside `run`, `this` is a map from component name to the component's fields,
+/// plus the entity index under `entity`. Changes made to `this` are written back to the
+/// components.
+///
+/// ```text
+/// fn query() { ["Position", "Velocity"] }
+///
+/// fn run() {
+///     this.Position.x += this.Velocity.x;
+/// }
+/// ```
+pub struct ScriptSystem {
+	name: String,
+	ast: AST,
+	query: Vec<String>,
+}
+
+impl ScriptSystem {
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn query(&self) -> &[String] {
+		&self.query
+	}
+}
+
+/// Bounds on the work a script can do, so that a runaway script fails instead of hanging or
+/// exhausting memory. Operation and call limits apply to every call of `query` and `run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScriptLimits {
+	pub max_operations: u64,
+	pub max_call_levels: usize,
+	pub max_string_size: usize,
+	pub max_array_size: usize,
+	pub max_map_size: usize,
+}
+
+impl Default for ScriptLimits {
+	fn default() -> Self {
+		Self {
+			max_operations: 100_000,
+			max_call_levels: 32,
+			max_string_size: 64 * 1024,
+			max_array_size: 10_000,
+			max_map_size: 10_000,
+		}
+	}
+}
+
+/// Compiles and runs script systems against components registered with
+/// `World::register_reflect_component`.
+pub struct ScriptEngine {
+	engine: Engine,
+	limits: ScriptLimits,
+	systems: Vec<ScriptSystem>,
+}
+
+impl Default for ScriptEngine {
+	fn default() -> Self {
+		let mut engine = Self {
+			engine: Engine::new(),
+			limits: ScriptLimits::default(),
+			systems: Vec::new(),
+		};
+		engine.set_limits(ScriptLimits::default());
+		engine
+	}
+}
+
+impl ScriptEngine {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The Rhai engine, to register additional functions and types.
+	pub const fn engine_mut(&mut self) -> &mut Engine {
+		&mut self.engine
+	}
+
+	pub const fn limits(&self) -> ScriptLimits {
+		self.limits
+	}
+
+	pub fn set_limits(&mut self, limits: ScriptLimits) -> &mut Self {
+		self.engine
+			.set_max_operations(limits.max_operations)
+			.set_max_call_levels(limits.max_call_levels)
+			.set_max_string_size(limits.max_string_size)
+			.set_max_array_size(limits.max_array_size)
+			.set_max_map_size(limits.max_map_size);
+		self.limits = limits;
+		self
+	}
+
+	/// Compile `source` and schedule it after the script systems added before it. The query may
+	/// not name a component `entity`, which is reserved for the entity index.
+	pub fn add_system(&mut self, name: impl Into<String>, source: &str) -> Result<()> {
+		let name = name.into();
+		let ast = self.engine.compile(source)?;
+		let query = self
+			.engine
+			.call_fn::<rhai::Array>(&mut Scope::new(), &ast, "query", ())?
+			.into_iter()
+			.map(|component| {
+				component
+					.into_string()
+					.map_err(|type_name| format!("Query of '{name}' contains a {type_name}, expected a string"))
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		if query.iter().any(|component| component == "entity") {
+			return Err(format!("Query of '{name}' names the component 'entity', which is reserved for the entity index").into());
+		}
+		self.systems.retain(|system| system.name != name);
+		self.systems.push(ScriptSystem { name, ast, query });
+		Ok(())
+	}
+
+	pub fn remove_system(&mut self, name: &str) {
+		self.systems.retain(|system| system.name != name);
+	}
+
+	pub fn systems(&self) -> &[ScriptSystem] {
+		&self.systems
+	}
+
+	/// Run the script system `name` over every matching entity.
+	///
+	/// The system stops at the first error. Entities processed before the error keep their
+	/// changes.
+	pub fn run_system(&self, name: &str, world: &World) -> Result<()> {
+		let system = self
+			.systems
+			.iter()
+			.find(|system| system.name == name)
+			.ok_or_else(|| ScriptNotFoundError { name: name.to_string() })?;
+		self.run_script(system, world)
+	}
+
+	/// Run every script system in order. A failing script does not prevent the scripts after it
+	/// from running; the errors of all failed scripts are returned.
+	pub fn run(&self, world: &World) -> Vec<ScriptError> {
+		self.systems
+			.iter()
+			.filter_map(|system| {
+				self.run_script(system, world).err().map(|error| ScriptError {
+					name: system.name.clone(),
+					message: error.to_string(),
+				})
+			})
+			.collect()
+	}
+
+	fn run_script(&self, system: &ScriptSystem, world: &World) -> Result<()> {
+		let entities = world
+			.enabled_entities()
+			.into_iter()
+			.filter(|entity| system.query.iter().all(|name| world.get_reflect(*entity, name).is_ok()))
+			.collect::<Vec<_>>();
+		entities.into_iter().try_for_each(|entity| self.run_entity(system, world, entity))
+	}
+
+	fn run_entity(&self, system: &ScriptSystem, world: &World, entity: Entity) -> Result<()> {
+		let mut components = Map::new();
+		for name in system.query.iter() {
+			let value = world.get_reflect(entity, name)?.get()?;
+			components.insert(name.into(), rhai::serde::to_dynamic(value)?);
+		}
+		components.insert("entity".into(), Dynamic::from(i64::from(*entity.index())));
+
+		let mut this = Dynamic::from_map(components);
+		let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut this);
+		let _ = self.engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &system.ast, "run", ())?;
+
+		let components = this.try_cast::<Map>().ok_or("Script replaced 'this' with a value that is not a map")?;
+		for name in system.query.iter() {
+			if let Some(value) = components.get(name.as_str()) {
+				let value = rhai::serde::from_dynamic::<serde_json::Value>(value)?;
+				world.get_reflect(entity, name)?.set(value)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Runs every script system, so that scripts can be scheduled alongside native systems. All
+/// scripts run even if some fail; the failures are reported together.
+impl System for ScriptEngine {
+	fn run(&mut self, world: &World) -> Result<()> {
+		let errors = Self::run(self, world);
+		if errors.is_empty() {
+			return Ok(());
+		}
+		Err(errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n").into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::impl_reflect;
+
+	#[derive(Debug, Default, PartialEq, Clone)]
+	struct Position {
+		x: f32,
+		y: f32,
+	}
+
+	impl_reflect!(Position { x, y });
+
+	#[derive(Debug, Default, PartialEq, Clone)]
+	struct Velocity {
+		x: f32,
+		y: f32,
+	}
+
+	impl_reflect!(Velocity { x, y });
+
+	const MOVEMENT: &str = r#"
+		fn query() { ["Position", "Velocity"] }
+
+		fn run() {
+			this.Position.x += this.Velocity.x;
+			this.Position.y += this.Velocity.y;
+		}
+	"#;
+
+	fn world() -> Result<(World, Vec<Entity>)> {
+		let mut world = World::new();
+		world.register_reflect_component::<Position>("Position");
+		world.register_reflect_component::<Velocity>("Velocity");
+		let entities = world.create_entities(2);
+		for entity in entities.iter() {
+			world.add_component(*entity, Position::default())?;
+		}
+		world.add_component(entities[0], Velocity { x: 1.0, y: 2.0 })?;
+		Ok((world, entities))
+	}
+
+	#[test]
+	fn script_systems_update_components() -> Result<()> {
+		let (world, entities) = world()?;
+		let mut scripts = ScriptEngine::new();
+		scripts.add_system("movement", MOVEMENT)?;
+		assert_eq!(scripts.systems()[0].query(), &["Position".to_string(), "Velocity".to_string()]);
+
+		scripts.run_system("movement", &world)?;
+		scripts.run_system("movement", &world)?;
+
+		assert_eq!(world.get_component::<Position>(entities[0]).as_deref(), Some(&Position { x: 2.0, y: 4.0 }));
+		assert_eq!(world.get_component::<Position>(entities[1]).as_deref(), Some(&Position::default()));
+		assert!(scripts.run_system("gravity", &world).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn failing_scripts_are_isolated() -> Result<()> {
+		let (world, entities) = world()?;
+		let mut scripts = ScriptEngine::new();
+		scripts.add_system("broken", r#"fn query() { ["Position"] } fn run() { throw "oops"; }"#)?;
+		scripts.add_system("movement", MOVEMENT)?;
+		assert!(scripts.add_system("invalid", "fn query() {").is_err());
+
+		let errors = scripts.run(&world);
+
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].name, "broken");
+		assert_eq!(world.get_component::<Position>(entities[0]).as_deref(), Some(&Position { x: 1.0, y: 2.0 }));
+
+		Ok(())
+	}
+
+	#[test]
+	fn runaway_scripts_are_stopped() -> Result<()> {
+		let (world, _) = world()?;
+		let mut scripts = ScriptEngine::new();
+		scripts.add_system("endless", r#"fn query() { ["Position"] } fn run() { loop {} }"#)?;
+		scripts.add_system("recursive", r#"fn query() { ["Position"] } fn deeper() { deeper() } fn run() { deeper() }"#)?;
+		assert!(scripts.add_system("reserved", r#"fn query() { ["entity"] } fn run() {}"#).is_err());
+
+		let errors = scripts.run(&world);
+
+		assert_eq!(errors.len(), 2);
+		assert!(errors[0].message.contains("Too many operations"));
+		assert_eq!(errors[1].name, "recursive");
+
+		Ok(())
+	}
+
+	#[test]
+	fn scripts_run_in_schedules() -> Result<()> {
+		let (mut world, entities) = world()?;
+		let mut scripts = ScriptEngine::new();
+		scripts.add_system("movement", MOVEMENT)?;
+
+		let mut schedule = crate::schedule::Schedule::new();
+		schedule.add_system(scripts);
+		schedule.run(&mut world)?;
+
+		assert_eq!(world.get_component::<Position>(entities[0]).as_deref(), Some(&Position { x: 1.0, y: 2.0 }));
+
+		Ok(())
+	}
+}
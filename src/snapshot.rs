@@ -0,0 +1,302 @@
+use self::error::SnapshotNotFoundError;
+use crate::{
+	error::Result,
+	vec::{HandleAllocator, Slot},
+	world::{Component, ComponentVec, World},
+};
+use std::{
+	any::{Any, TypeId},
+	cell::RefCell,
+	collections::{HashMap, VecDeque},
+	rc::Rc,
+};
+
+pub mod error {
+	#[derive(Debug)]
+	pub struct SnapshotNotFoundError {
+		pub tick: u64,
+	}
+
+	impl std::error::Error for SnapshotNotFoundError {}
+
+	impl std::fmt::Display for SnapshotNotFoundError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "No snapshot was recorded for tick '{}'.", self.tick)
+		}
+	}
+}
+
+/// A copy of the allocator, every cloneable component column and every cloneable resource of a
+/// `World`.
+pub struct WorldSnapshot {
+	allocator: HandleAllocator,
+	components: HashMap<TypeId, ComponentVec>,
+	resources: HashMap<TypeId, Box<dyn Any>>,
+	checksum: u64,
+}
+
+impl WorldSnapshot {
+	/// The `World::state_hash` of the world at the time the snapshot was taken, for desync
+	/// detection.
+	pub const fn checksum(&self) -> u64 {
+		self.checksum
+	}
+}
+
+impl World {
+	/// Capture the allocator, the component columns registered as cloneable and the resources
+	/// registered as cloneable.
+	pub fn snapshot(&self) -> WorldSnapshot {
+		let components = self
+			.components
+			.iter()
+			.filter_map(|(type_id, component_vec)| self.clone_component_vec(type_id, &component_vec.borrow()).map(|column| (*type_id, column)))
+			.collect::<HashMap<_, _>>();
+
+		let resources = {
+			let resource_map = self.resources.borrow();
+			self.registry
+				.resource_types()
+				.filter_map(|type_id| {
+					resource_map
+						.get_by_id(type_id)
+						.and_then(|resource| self.registry.clone_resource(type_id, resource))
+						.map(|resource| (*type_id, resource))
+				})
+				.collect::<HashMap<_, _>>()
+		};
+
+		WorldSnapshot {
+			allocator: self.allocator.clone(),
+			components,
+			resources,
+			checksum: self.state_hash(),
+		}
+	}
+
+	/// Roll the world back to the state captured in `snapshot`.
+	///
+	/// Component columns that were not registered as cloneable, and dynamic components, keep the
+	/// values of entities that are alive in the snapshot. Their values for every other entity are
+	/// dropped, so that a handle reused after the rollback does not see them.
+	pub fn restore(&mut self, snapshot: &WorldSnapshot) {
+		self.allocator = snapshot.allocator.clone();
+
+		let untracked = self
+			.components
+			.iter()
+			.filter(|(type_id, _)| !snapshot.components.contains_key(type_id))
+			.map(|(_, column)| column)
+			.chain(self.dynamic_components.values());
+		for column in untracked {
+			clear_dead_slots(&self.allocator, &mut column.borrow_mut());
+		}
+
+		for (type_id, column) in snapshot.components.iter() {
+			if let Some(column) = self.clone_component_vec(type_id, column) {
+				*self
+					.components
+					.entry(*type_id)
+					.or_insert_with(|| Rc::new(RefCell::new(ComponentVec::default())))
+					.borrow_mut() = column;
+			}
+		}
+		self.rebuild_indices();
+
+		let mut resource_map = self.resources.borrow_mut();
+		self.registry.resource_types().for_each(|type_id| {
+			match snapshot
+				.resources
+				.get(type_id)
+				.and_then(|resource| self.registry.clone_resource(type_id, resource.as_ref()))
+			{
+				Some(resource) => resource_map.insert_by_id(*type_id, resource),
+				None => resource_map.remove_by_id(type_id),
+			}
+		});
+	}
+}
+
+fn clear_dead_slots(allocator: &HandleAllocator, column: &mut ComponentVec) {
+	for (index, slot) in column.iter_mut().enumerate() {
+		let alive = |slot: &Slot<Component>| allocator.handle_at(index).is_some_and(|handle| handle.generation() == slot.generation());
+		if slot.as_ref().is_some_and(|slot| !alive(slot)) {
+			*slot = None;
+		}
+	}
+}
+
+/// A fixed-capacity ring buffer of snapshots keyed by simulation tick.
+pub struct SnapshotBuffer {
+	capacity: usize,
+	snapshots: VecDeque<(u64, WorldSnapshot)>,
+}
+
+impl SnapshotBuffer {
+	pub fn new(capacity: usize) -> Self {
+		let capacity = capacity.max(1);
+		Self {
+			capacity,
+			snapshots: VecDeque::with_capacity(capacity),
+		}
+	}
+
+	pub const fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	pub fn len(&self) -> usize {
+		self.snapshots.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.snapshots.is_empty()
+	}
+
+	/// Record a snapshot for `tick`, evicting the oldest snapshot once the buffer is full.
+	/// Snapshots for `tick` or any later tick are replaced, since they belong to a discarded
+	/// timeline.
+	pub fn push(&mut self, tick: u64, snapshot: WorldSnapshot) {
+		self.snapshots.retain(|(recorded_tick, _)| *recorded_tick < tick);
+		if self.snapshots.len() == self.capacity {
+			self.snapshots.pop_front();
+		}
+		self.snapshots.push_back((tick, snapshot));
+	}
+
+	/// Capture the world and record it for `tick`.
+	pub fn record(&mut self, tick: u64, world: &World) {
+		self.push(tick, world.snapshot());
+	}
+
+	pub fn get(&self, tick: u64) -> Option<&WorldSnapshot> {
+		self.snapshots.iter().find(|(recorded_tick, _)| *recorded_tick == tick).map(|(_, snapshot)| snapshot)
+	}
+
+	pub fn checksum(&self, tick: u64) -> Option<u64> {
+		self.get(tick).map(WorldSnapshot::checksum)
+	}
+
+	pub fn latest_tick(&self) -> Option<u64> {
+		self.snapshots.back().map(|(tick, _)| *tick)
+	}
+
+	/// Restore the world to the snapshot recorded for `tick` and discard every later snapshot.
+	pub fn rollback(&mut self, tick: u64, world: &mut World) -> Result<()> {
+		let snapshot = self.get(tick).ok_or(SnapshotNotFoundError { tick })?;
+		world.restore(snapshot);
+		self.snapshots.retain(|(recorded_tick, _)| *recorded_tick <= tick);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+	struct Position {
+		x: i32,
+		y: i32,
+	}
+
+	#[derive(Debug, PartialEq, Clone)]
+	struct Tick(u64);
+
+	#[test]
+	fn snapshot_and_restore() -> Result<()> {
+		let mut world = World::new();
+		world.register_cloneable_component::<Position>();
+		world.register_cloneable_resource::<Tick>();
+
+		let entity = world.create_entity();
+		world.add_component(entity, Position { x: 1, y: 2 })?;
+		world.resources().borrow_mut().insert(Tick(1));
+		let snapshot = world.snapshot();
+
+		world.get_component_mut::<Position>(entity).unwrap().x = 10;
+		world.resources().borrow_mut().insert(Tick(2));
+		let spawned = world.create_entity();
+		world.add_component(spawned, Position::default())?;
+
+		world.restore(&snapshot);
+
+		assert_eq!(world.get_component::<Position>(entity).as_deref(), Some(&Position { x: 1, y: 2 }));
+		assert_eq!(world.resources().borrow().get::<Tick>(), Some(&Tick(1)));
+		assert!(!world.entity_exists(spawned));
+		assert_eq!(world.snapshot().checksum(), snapshot.checksum());
+
+		Ok(())
+	}
+
+	#[test]
+	fn restore_drops_untracked_components_of_rolled_back_entities() -> Result<()> {
+		let mut world = World::new();
+		let kept = world.create_entity();
+		world.add_component(kept, Tick(1))?;
+		let snapshot = world.snapshot();
+
+		let spawned = world.create_entity();
+		world.add_component(spawned, Tick(2))?;
+		world.restore(&snapshot);
+
+		let reused = world.create_entity();
+		assert_eq!(reused.index(), spawned.index());
+		assert_eq!(world.get_component::<Tick>(kept).as_deref(), Some(&Tick(1)));
+		assert_eq!(world.get_component::<Tick>(reused).as_deref(), None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn restore_removes_resources_inserted_later() {
+		let mut world = World::new();
+		world.register_cloneable_resource::<Tick>();
+		let snapshot = world.snapshot();
+
+		world.resources().borrow_mut().insert(Tick(5));
+		world.restore(&snapshot);
+
+		assert_eq!(world.resources().borrow().get::<Tick>(), None);
+	}
+
+	#[test]
+	fn checksum_detects_divergence() -> Result<()> {
+		let mut world = World::new();
+		world.register_cloneable_component::<Position>();
+		world.register_hashable_component::<Position>("Position");
+		let entity = world.create_entity();
+		world.add_component(entity, Position::default())?;
+		let checksum = world.snapshot().checksum();
+
+		world.get_component_mut::<Position>(entity).unwrap().x = 1;
+
+		assert_ne!(world.snapshot().checksum(), checksum);
+
+		Ok(())
+	}
+
+	#[test]
+	fn ring_buffer_rollback() -> Result<()> {
+		let mut world = World::new();
+		world.register_cloneable_component::<Position>();
+		let entity = world.create_entity();
+		world.add_component(entity, Position::default())?;
+
+		let mut snapshots = SnapshotBuffer::new(3);
+		for tick in 0..5 {
+			world.get_component_mut::<Position>(entity).unwrap().x = tick as i32;
+			snapshots.record(tick, &world);
+		}
+
+		assert_eq!(snapshots.len(), 3);
+		assert!(snapshots.get(1).is_none());
+		assert!(snapshots.rollback(0, &mut world).is_err());
+
+		snapshots.rollback(2, &mut world)?;
+		assert_eq!(world.get_component::<Position>(entity).unwrap().x, 2);
+		assert_eq!(snapshots.latest_tick(), Some(2));
+
+		Ok(())
+	}
+}
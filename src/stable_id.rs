@@ -0,0 +1,215 @@
+use self::error::DuplicateStableIdError;
+use crate::{
+	error::Result,
+	world::{Component, Entity, World},
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{any::TypeId, collections::HashMap};
+
+pub mod error {
+	use super::StableId;
+	use crate::world::Entity;
+
+	#[derive(Debug)]
+	pub struct DuplicateStableIdError {
+		pub id: StableId,
+		pub entity: Entity,
+	}
+
+	impl std::error::Error for DuplicateStableIdError {}
+
+	impl std::fmt::Display for DuplicateStableIdError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Stable id '{}' already belongs to entity {:?}.", self.id, self.entity)
+		}
+	}
+}
+
+/// An entity id that does not depend on allocation order, such as a UUID or a user supplied
+/// number, for use in save files and network messages.
+///
+/// Serialized as a 32 digit hexadecimal string so that the full 128 bits survive formats that
+/// cannot represent large integers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StableId(pub u128);
+
+impl From<u64> for StableId {
+	fn from(id: u64) -> Self {
+		Self(u128::from(id))
+	}
+}
+
+impl From<u128> for StableId {
+	fn from(id: u128) -> Self {
+		Self(id)
+	}
+}
+
+impl std::fmt::Display for StableId {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{:032x}", self.0)
+	}
+}
+
+impl Serialize for StableId {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		serializer.serialize_str(&self.to_string())
+	}
+}
+
+impl<'de> Deserialize<'de> for StableId {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+		let id = String::deserialize(deserializer)?;
+		u128::from_str_radix(&id, 16).map(Self).map_err(D::Error::custom)
+	}
+}
+
+impl World {
+	/// The entity holding the `StableId` component `id`, if any.
+	///
+	/// Ids written in place, for example through `get_component_mut`, are re-indexed here. If such
+	/// a write gave several entities the same id, the one with the lowest index is returned.
+	pub fn entity_by_stable_id(&self, id: StableId) -> Option<Entity> {
+		self.refresh_indices();
+		self.stable_ids.borrow().get(&id).copied().filter(|entity| self.entity_exists(*entity))
+	}
+
+	/// Keep the stable id index in step with a `StableId` being assigned to or removed from
+	/// `entity`. Fails without touching the index if the id already belongs to another entity.
+	pub(crate) fn index_stable_id(&mut self, entity: Entity, type_id: TypeId, value: Option<&Component>) -> Result<()> {
+		if type_id != TypeId::of::<StableId>() {
+			return Ok(());
+		}
+
+		let id = value.and_then(|component| component.downcast_ref::<StableId>()).copied();
+		if let Some(id) = id {
+			if let Some(owner) = self.entity_by_stable_id(id).filter(|owner| *owner != entity) {
+				return Err(Box::new(DuplicateStableIdError { id, entity: owner }));
+			}
+		}
+
+		self.unindex_stable_id(entity);
+		if let Some(id) = id {
+			self.stable_ids.get_mut().insert(id, entity);
+		}
+		Ok(())
+	}
+
+	/// Remove the index entry of the `StableId` of `entity`, if the entry belongs to `entity`.
+	pub(crate) fn unindex_stable_id(&mut self, entity: Entity) {
+		let id = self.get_component::<StableId>(entity).as_deref().copied();
+		if let Some(id) = id {
+			let stable_ids = self.stable_ids.get_mut();
+			if stable_ids.get(&id) == Some(&entity) {
+				stable_ids.remove(&id);
+			}
+		}
+	}
+
+	/// Rebuild the stable id index from the `StableId` column. Returns false without touching the
+	/// index if the column is borrowed mutably.
+	pub(crate) fn rebuild_stable_ids(&self) -> bool {
+		let Some(column) = self.components.get(&TypeId::of::<StableId>()) else {
+			self.stable_ids.borrow_mut().clear();
+			return true;
+		};
+		let Ok(column) = column.try_borrow() else {
+			return false;
+		};
+		let mut stable_ids = HashMap::new();
+		for entity in self.allocator.allocated_handles() {
+			if let Some(id) = column.get(entity).and_then(|id| id.downcast_ref::<StableId>()) {
+				stable_ids.entry(*id).or_insert(entity);
+			}
+		}
+		*self.stable_ids.borrow_mut() = stable_ids;
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scene::Scene;
+
+	#[test]
+	fn index_follows_components() -> Result<()> {
+		let mut world = World::new();
+		let entity = world.create_entity();
+		world.add_component(entity, StableId::from(7_u64))?;
+		assert_eq!(world.entity_by_stable_id(StableId(7)), Some(entity));
+
+		world.add_component(entity, StableId(8))?;
+		assert_eq!(world.entity_by_stable_id(StableId(7)), None);
+		assert_eq!(world.entity_by_stable_id(StableId(8)), Some(entity));
+
+		let other = world.create_entity();
+		assert!(world.add_component(other, StableId(8)).is_err());
+		assert!(world.get_component::<StableId>(other).is_none());
+
+		world.remove_entity(entity);
+		assert_eq!(world.entity_by_stable_id(StableId(8)), None);
+		world.add_component(other, StableId(8))?;
+		assert_eq!(world.entity_by_stable_id(StableId(8)), Some(other));
+
+		world.remove_component::<StableId>(other)?;
+		assert_eq!(world.entity_by_stable_id(StableId(8)), None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn index_follows_in_place_writes() -> Result<()> {
+		let mut world = World::new();
+		let entities = world.create_entities(2);
+		world.add_component(entities[0], StableId(7))?;
+		world.add_component(entities[1], StableId(8))?;
+
+		*world.get_component_mut::<StableId>(entities[1]).unwrap() = StableId(7);
+		assert_eq!(world.entity_by_stable_id(StableId(8)), None);
+		assert_eq!(world.entity_by_stable_id(StableId(7)), Some(entities[0]));
+
+		world.remove_entity(entities[1]);
+		assert_eq!(world.entity_by_stable_id(StableId(7)), Some(entities[0]));
+
+		Ok(())
+	}
+
+	#[test]
+	fn stable_ids_survive_scenes() -> Result<()> {
+		let id = StableId(u128::MAX - 1);
+		let mut source = World::new();
+		source.register_serializable_component::<StableId>("StableId");
+		source.create_entities(3);
+		let entity = source.create_entity();
+		source.add_component(entity, id)?;
+		let scene = Scene::from_json(&source.save_scene()?.to_json()?)?;
+
+		let mut world = World::new();
+		world.register_serializable_component::<StableId>("StableId");
+		let entity_map = world.load_scene(&scene)?;
+		assert_eq!(world.entity_by_stable_id(id), entity_map.get(entity));
+
+		assert!(world.load_scene(&scene).is_err());
+		assert_eq!(world.entity_by_stable_id(id), entity_map.get(entity));
+
+		Ok(())
+	}
+
+	#[test]
+	fn restore_rebuilds_index() -> Result<()> {
+		let mut world = World::new();
+		world.register_cloneable_component::<StableId>();
+		let entity = world.create_entity();
+		world.add_component(entity, StableId(1))?;
+		let snapshot = world.snapshot();
+
+		world.add_component(entity, StableId(2))?;
+		world.restore(&snapshot);
+
+		assert_eq!(world.entity_by_stable_id(StableId(1)), Some(entity));
+		assert_eq!(world.entity_by_stable_id(StableId(2)), None);
+
+		Ok(())
+	}
+}
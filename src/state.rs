@@ -0,0 +1,264 @@
+use crate::{
+	error::Result,
+	schedule::{Schedule, System},
+	world::World,
+};
+use std::any::Any;
+
+/// Types that can be used as application states, typically fieldless enums.
+pub trait States: Clone + PartialEq + Eq + std::fmt::Debug + 'static {}
+
+impl<S: Clone + PartialEq + Eq + std::fmt::Debug + 'static> States for S {}
+
+/// The current state of type `S`, inserted as a resource by `App::add_state`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State<S: States>(S);
+
+impl<S: States> State<S> {
+	pub const fn get(&self) -> &S {
+		&self.0
+	}
+}
+
+/// The state to transition to at the start of the next update, inserted as a resource by
+/// `App::add_state`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NextState<S: States>(Option<S>);
+
+impl<S: States> NextState<S> {
+	pub fn set(&mut self, state: S) {
+		self.0 = Some(state);
+	}
+
+	pub const fn get(&self) -> Option<&S> {
+		self.0.as_ref()
+	}
+}
+
+/// Marks an entity as belonging to a state. The entity is removed when that state is exited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateScoped<S: States>(pub S);
+
+/// Runs systems when the state is entered, including the initial state on the first update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnEnter<S: States>(pub S);
+
+/// Runs systems when the state is exited, before its state-scoped entities are removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnExit<S: States>(pub S);
+
+/// Runs systems on a transition from one state to another, after `OnExit` and before `OnEnter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnTransition<S: States> {
+	pub from: S,
+	pub to: S,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateHook<S: States> {
+	Enter(S),
+	Exit(S),
+	Transition { from: S, to: S },
+}
+
+impl<S: States> From<OnEnter<S>> for StateHook<S> {
+	fn from(hook: OnEnter<S>) -> Self {
+		Self::Enter(hook.0)
+	}
+}
+
+impl<S: States> From<OnExit<S>> for StateHook<S> {
+	fn from(hook: OnExit<S>) -> Self {
+		Self::Exit(hook.0)
+	}
+}
+
+impl<S: States> From<OnTransition<S>> for StateHook<S> {
+	fn from(hook: OnTransition<S>) -> Self {
+		Self::Transition { from: hook.from, to: hook.to }
+	}
+}
+
+/// Applies the queued transitions of one state type and runs its hook systems.
+pub(crate) trait StateDriver {
+	fn apply(&mut self, world: &mut World) -> Result<()>;
+
+	fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+pub(crate) struct StateTransitions<S: States> {
+	entered: bool,
+	hooks: Vec<(StateHook<S>, Schedule)>,
+}
+
+impl<S: States> Default for StateTransitions<S> {
+	fn default() -> Self {
+		Self {
+			entered: false,
+			hooks: Vec::new(),
+		}
+	}
+}
+
+impl<S: States> StateTransitions<S> {
+	pub(crate) fn add_system(&mut self, hook: StateHook<S>, system: impl System + 'static) {
+		match self.hooks.iter_mut().find(|(existing, _)| *existing == hook) {
+			Some((_, schedule)) => {
+				schedule.add_system(system);
+			},
+			None => {
+				let mut schedule = Schedule::new();
+				schedule.add_system(system);
+				self.hooks.push((hook, schedule));
+			},
+		}
+	}
+
+	fn run_hook(&mut self, hook: &StateHook<S>, world: &mut World) -> Result<()> {
+		match self.hooks.iter_mut().find(|(existing, _)| existing == hook) {
+			Some((_, schedule)) => schedule.run(world),
+			None => Ok(()),
+		}
+	}
+}
+
+impl<S: States> StateDriver for StateTransitions<S> {
+	fn apply(&mut self, world: &mut World) -> Result<()> {
+		let (current, next) = {
+			let mut resources = world.resources().borrow_mut();
+			let Some(current) = resources.get::<State<S>>().map(|state| state.0.clone()) else {
+				return Ok(());
+			};
+			let pending = resources.get::<NextState<S>>().is_some_and(|next| next.0.is_some());
+			let next = if pending {
+				resources.get_mut::<NextState<S>>().and_then(|next| next.0.take())
+			} else {
+				None
+			};
+			(current, next)
+		};
+
+		if !self.entered {
+			self.entered = true;
+			self.run_hook(&StateHook::Enter(current.clone()), world)?;
+		}
+
+		let Some(next) = next.filter(|next| *next != current) else {
+			return Ok(());
+		};
+
+		self.run_hook(&StateHook::Exit(current.clone()), world)?;
+		world.remove_state_scoped_entities(&current);
+		self.run_hook(&StateHook::Transition { from: current, to: next.clone() }, world)?;
+		world.resources().borrow_mut().insert(State(next.clone()));
+		self.run_hook(&StateHook::Enter(next), world)
+	}
+
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		self
+	}
+}
+
+impl World {
+	/// Insert the `State` and `NextState` resources for `S`, starting in `initial`.
+	pub fn init_state<S: States>(&self, initial: S) {
+		let mut resources = self.resources().borrow_mut();
+		resources.insert(State(initial));
+		resources.insert(NextState::<S>(None));
+	}
+
+	fn remove_state_scoped_entities<S: States>(&mut self, state: &S) {
+		let entities = self
+			.allocator()
+			.allocated_handles()
+			.into_iter()
+			.filter(|entity| self.get_component::<StateScoped<S>>(*entity).is_some_and(|scoped| scoped.0 == *state))
+			.collect::<Vec<_>>();
+		self.remove_entities(&entities);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::app::{App, Runner};
+
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	enum GameState {
+		Loading,
+		Menu,
+		Playing,
+	}
+
+	#[derive(Debug, Default, PartialEq, Eq, Clone)]
+	struct Log(Vec<&'static str>);
+
+	fn log(entry: &'static str) -> impl FnMut(&World) -> Result<()> {
+		move |world: &World| {
+			world.resources().borrow_mut().get_mut::<Log>().unwrap().0.push(entry);
+			Ok(())
+		}
+	}
+
+	fn go_to(state: GameState) -> impl FnMut(&World) -> Result<()> {
+		move |world: &World| {
+			world.resources().borrow_mut().get_mut::<NextState<GameState>>().unwrap().set(state);
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn transitions_run_hooks() -> Result<()> {
+		let mut app = App::new();
+		app.insert_resource(Log::default())
+			.add_state(GameState::Loading)
+			.add_state_system(OnEnter(GameState::Loading), log("enter loading"))
+			.add_state_system(OnEnter(GameState::Loading), go_to(GameState::Menu))
+			.add_state_system(OnExit(GameState::Loading), log("exit loading"))
+			.add_state_system(
+				OnTransition {
+					from: GameState::Loading,
+					to: GameState::Menu,
+				},
+				log("loading to menu"),
+			)
+			.add_state_system(OnEnter(GameState::Menu), log("enter menu"))
+			.add_state_system(OnEnter(GameState::Playing), log("enter playing"));
+
+		app.set_runner(Runner::Iterations(3)).run()?;
+
+		let resources = app.world().resources().borrow();
+		assert_eq!(
+			resources.get::<Log>().unwrap().0,
+			vec!["enter loading", "exit loading", "loading to menu", "enter menu"]
+		);
+		assert_eq!(resources.get::<State<GameState>>().unwrap().get(), &GameState::Menu);
+
+		Ok(())
+	}
+
+	#[test]
+	fn state_scoped_entities() -> Result<()> {
+		let mut app = App::new();
+		app.add_state(GameState::Menu);
+		let (scoped, unscoped) = {
+			let world = app.world_mut();
+			let scoped = world.create_entity();
+			world.add_component(scoped, StateScoped(GameState::Menu))?;
+			let unscoped = world.create_entity();
+			world.add_component(unscoped, StateScoped(GameState::Playing))?;
+			(scoped, unscoped)
+		};
+
+		app.update()?;
+		assert!(app.world().entity_exists(scoped));
+
+		app.add_system(go_to(GameState::Playing));
+		app.update()?;
+		app.update()?;
+		assert!(!app.world().entity_exists(scoped));
+		assert!(app.world().entity_exists(unscoped));
+
+		Ok(())
+	}
+}
@@ -0,0 +1,205 @@
+use std::time::{Duration, Instant};
+
+/// Where `Time` takes the duration of each frame from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clock {
+	/// Measure the wall clock time between updates. The first update has a delta of zero.
+	Real { last_update: Option<Instant> },
+
+	/// Advance by the same duration every update, for headless and deterministic runs.
+	Manual(Duration),
+}
+
+impl Default for Clock {
+	fn default() -> Self {
+		Self::Real { last_update: None }
+	}
+}
+
+/// Frame timing, inserted as a resource by `App` and updated once at the start of every update.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Time {
+	delta: Duration,
+	elapsed: Duration,
+	frame_count: u64,
+	clock: Clock,
+}
+
+impl Time {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// A time that advances by `delta` on every update, regardless of how long frames take.
+	pub fn manual(delta: Duration) -> Self {
+		Self {
+			clock: Clock::Manual(delta),
+			..Self::default()
+		}
+	}
+
+	/// Start a new frame, taking its duration from the clock. Returns the frame's delta.
+	pub fn update(&mut self) -> Duration {
+		let delta = match &mut self.clock {
+			Clock::Real { last_update } => {
+				let now = Instant::now();
+				let delta = last_update.map_or(Duration::ZERO, |last_update| now - last_update);
+				*last_update = Some(now);
+				delta
+			},
+			Clock::Manual(delta) => *delta,
+		};
+		self.advance(delta);
+		delta
+	}
+
+	/// Start a new frame that lasted `delta`.
+	pub fn advance(&mut self, delta: Duration) {
+		self.delta = delta;
+		self.elapsed += delta;
+		self.frame_count += 1;
+	}
+
+	pub const fn delta(&self) -> Duration {
+		self.delta
+	}
+
+	pub const fn delta_seconds(&self) -> f32 {
+		self.delta.as_secs_f32()
+	}
+
+	pub const fn elapsed(&self) -> Duration {
+		self.elapsed
+	}
+
+	pub const fn elapsed_seconds(&self) -> f32 {
+		self.elapsed.as_secs_f32()
+	}
+
+	/// The number of frames started so far.
+	pub const fn frame_count(&self) -> u64 {
+		self.frame_count
+	}
+
+	pub const fn clock(&self) -> Clock {
+		self.clock
+	}
+}
+
+/// Drives the fixed timestep systems of an `App`, inserted as a resource.
+///
+/// Frame time is accumulated and spent in steps of `step`, so the fixed systems run zero or more
+/// times per frame. At most `max_steps` run per frame; beyond that the backlog is dropped so a
+/// slow frame cannot trigger ever longer catch-up. `alpha` is the fraction of a step left over
+/// after the last one, for render systems to interpolate between the previous and the current
+/// fixed state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedTime {
+	step: Duration,
+	max_steps: u32,
+	accumulator: Duration,
+	elapsed: Duration,
+	steps: u32,
+	alpha: f32,
+}
+
+impl Default for FixedTime {
+	fn default() -> Self {
+		Self::new(Duration::from_secs(1) / 60)
+	}
+}
+
+impl FixedTime {
+	/// Panics if `step` is zero.
+	pub fn new(step: Duration) -> Self {
+		assert!(!step.is_zero(), "The fixed timestep must be longer than zero");
+		Self {
+			step,
+			max_steps: 5,
+			accumulator: Duration::ZERO,
+			elapsed: Duration::ZERO,
+			steps: 0,
+			alpha: 0.0,
+		}
+	}
+
+	pub const fn with_max_steps(mut self, max_steps: u32) -> Self {
+		self.max_steps = max_steps;
+		self
+	}
+
+	/// Add a frame's duration and return how many fixed steps to run for it.
+	pub fn accumulate(&mut self, delta: Duration) -> u32 {
+		self.accumulator += delta;
+		let available = self.accumulator.as_nanos() / self.step.as_nanos();
+		let steps = u32::try_from(available).unwrap_or(u32::MAX).min(self.max_steps);
+
+		self.accumulator = if available > u128::from(steps) {
+			Duration::from_nanos((self.accumulator.as_nanos() % self.step.as_nanos()) as u64)
+		} else {
+			self.accumulator - self.step * steps
+		};
+		self.elapsed += self.step * steps;
+		self.steps = steps;
+		self.alpha = self.accumulator.as_secs_f32() / self.step.as_secs_f32();
+		steps
+	}
+
+	pub const fn step(&self) -> Duration {
+		self.step
+	}
+
+	pub const fn step_seconds(&self) -> f32 {
+		self.step.as_secs_f32()
+	}
+
+	pub const fn max_steps(&self) -> u32 {
+		self.max_steps
+	}
+
+	/// The simulated time covered by all fixed steps run so far.
+	pub const fn elapsed(&self) -> Duration {
+		self.elapsed
+	}
+
+	/// The number of fixed steps run for the current frame.
+	pub const fn steps(&self) -> u32 {
+		self.steps
+	}
+
+	/// How far the current frame is between the last fixed step and the next one, from 0 to 1.
+	pub const fn alpha(&self) -> f32 {
+		self.alpha
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn manual_time() {
+		let mut time = Time::manual(Duration::from_millis(10));
+		time.update();
+		time.update();
+		assert_eq!(time.delta(), Duration::from_millis(10));
+		assert_eq!(time.elapsed(), Duration::from_millis(20));
+		assert_eq!(time.frame_count(), 2);
+	}
+
+	#[test]
+	fn fixed_steps() {
+		let mut fixed_time = FixedTime::new(Duration::from_millis(10)).with_max_steps(3);
+
+		assert_eq!(fixed_time.accumulate(Duration::from_millis(5)), 0);
+		assert_eq!(fixed_time.alpha(), 0.5);
+
+		assert_eq!(fixed_time.accumulate(Duration::from_millis(20)), 2);
+		assert_eq!(fixed_time.alpha(), 0.5);
+		assert_eq!(fixed_time.elapsed(), Duration::from_millis(20));
+
+		assert_eq!(fixed_time.accumulate(Duration::from_millis(100)), 3);
+		assert_eq!(fixed_time.alpha(), 0.5);
+		assert_eq!(fixed_time.elapsed(), Duration::from_millis(50));
+	}
+}
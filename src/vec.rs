@@ -1,7 +1,12 @@
 use self::error::GenerationError;
 use crate::error::Result;
 use serde::{Deserialize, Serialize};
-use std::ops::{Deref, DerefMut};
+use std::{
+	collections::VecDeque,
+	hash::{Hash, Hasher},
+	ops::{Deref, DerefMut},
+	sync::atomic::{AtomicUsize, Ordering},
+};
 
 pub mod error {
 	use super::*;
@@ -35,20 +40,41 @@ pub mod error {
 
 pub type SlotVec<T> = Vec<Option<Slot<T>>>;
 
-#[derive(Default, Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[derive(Default, Debug, PartialEq, Eq, Copy, Clone, Hash, Serialize, Deserialize)]
 pub struct Handle {
-	index: usize,
-	generation: usize,
+	index: u32,
+	generation: u32,
 }
 
 impl Handle {
-	pub const fn index(&self) -> &usize {
+	pub const fn new(index: u32, generation: u32) -> Self {
+		Self { index, generation }
+	}
+
+	pub const fn index(&self) -> &u32 {
 		&self.index
 	}
 
-	pub const fn generation(&self) -> &usize {
+	pub const fn generation(&self) -> &u32 {
 		&self.generation
 	}
+
+	/// Pack the handle into a `u64`, with the generation in the high 32 bits and the index in the
+	/// low 32 bits.
+	pub const fn to_bits(&self) -> u64 {
+		((self.generation as u64) << 32) | self.index as u64
+	}
+
+	pub const fn from_bits(bits: u64) -> Self {
+		Self {
+			index: bits as u32,
+			generation: (bits >> 32) as u32,
+		}
+	}
+
+	const fn slot(&self) -> usize {
+		self.index as usize
+	}
 }
 
 #[derive(Serialize, Deserialize)]
@@ -57,16 +83,29 @@ pub struct GenerationalVec<T> {
 }
 
 impl<T> GenerationalVec<T> {
-	pub fn new(elements: SlotVec<T>) -> Self {
+	pub const fn new(elements: SlotVec<T>) -> Self {
 		Self { elements }
 	}
 
 	pub fn insert(&mut self, handle: Handle, value: T) -> Result<()> {
-		while self.elements.len() <= handle.index {
+		while self.elements.len() <= handle.slot() {
 			self.elements.push(None);
 		}
 
-		let previous_generation = match self.elements.get(handle.index) {
+		self.check_generation(handle)?;
+
+		self.elements[handle.slot()] = Some(Slot {
+			value,
+			generation: handle.generation,
+		});
+
+		Ok(())
+	}
+
+	/// Fail if the slot for `handle` holds a value of a newer generation, in which case `insert`
+	/// would fail as well.
+	pub fn check_generation(&self, handle: Handle) -> Result<()> {
+		let previous_generation = match self.elements.get(handle.slot()) {
 			Some(Some(entry)) => entry.generation,
 			_ => 0,
 		};
@@ -75,35 +114,47 @@ impl<T> GenerationalVec<T> {
 			return Err(Box::new(GenerationError { handle }));
 		}
 
-		self.elements[handle.index] = Some(Slot {
-			value,
-			generation: handle.generation,
-		});
-
 		Ok(())
 	}
 
+	/// Grow the vector to hold at least `length` slots, so that inserting any handle with a lower
+	/// index does not reallocate.
+	pub fn grow_to(&mut self, length: usize) {
+		if self.elements.len() < length {
+			self.elements.resize_with(length, || None);
+		}
+	}
+
 	pub fn remove(&mut self, handle: Handle) {
-		if let Some(e) = self.elements.get_mut(handle.index) {
+		if let Some(e) = self.elements.get_mut(handle.slot()) {
 			*e = None;
 		}
 	}
 
+	/// Move the value for `handle` out of the vector, if it holds one of the same generation.
+	pub fn take(&mut self, handle: Handle) -> Option<T> {
+		let slot = self.elements.get_mut(handle.slot())?;
+		if slot.as_ref()?.generation != handle.generation {
+			return None;
+		}
+		slot.take().map(|entry| entry.value)
+	}
+
 	pub fn get(&self, handle: Handle) -> Option<&T> {
-		if handle.index >= self.elements.len() {
+		if handle.slot() >= self.elements.len() {
 			return None;
 		}
-		self.elements[handle.index]
+		self.elements[handle.slot()]
 			.as_ref()
 			.filter(|c| c.generation == handle.generation)
 			.map(|entry| &entry.value)
 	}
 
 	pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
-		if handle.index >= self.elements.len() {
+		if handle.slot() >= self.elements.len() {
 			return None;
 		}
-		self.elements[handle.index]
+		self.elements[handle.slot()]
 			.as_mut()
 			.filter(|c| c.generation == handle.generation)
 			.map(|entry| &mut entry.value)
@@ -127,15 +178,15 @@ impl<T> DerefMut for GenerationalVec<T> {
 #[derive(Serialize, Deserialize)]
 pub struct Slot<T> {
 	value: T,
-	generation: usize,
+	generation: u32,
 }
 
 impl<T> Slot<T> {
-	pub const fn new(value: T, generation: usize) -> Self {
+	pub const fn new(value: T, generation: u32) -> Self {
 		Self { value, generation }
 	}
 
-	pub const fn generation(&self) -> &usize {
+	pub const fn generation(&self) -> &u32 {
 		&self.generation
 	}
 }
@@ -154,16 +205,65 @@ impl<T> DerefMut for Slot<T> {
 	}
 }
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default, Clone, Hash, Serialize, Deserialize)]
 pub struct Allocation {
 	allocated: bool,
-	generation: usize,
+	generation: u32,
+	#[serde(default)]
+	disabled: bool,
+}
+
+/// The order in which deallocated indices are handed out again.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FreeListOrder {
+	/// Reuse the most recently deallocated index first, keeping the set of live indices compact.
+	#[default]
+	Lifo,
+	/// Reuse the least recently deallocated index first, spreading generation increments across
+	/// slots.
+	Fifo,
+}
+
+/// What happens to an index whose generation cannot be incremented any further.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GenerationOverflow {
+	/// Stop reusing the index, so that a stale handle can never match a live one.
+	#[default]
+	Retire,
+	/// Wrap the generation back to zero and keep reusing the index.
+	Wrap,
 }
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct HandleAllocator {
 	allocations: Vec<Allocation>,
-	available_handles: Vec<usize>,
+	available_handles: VecDeque<u32>,
+	#[serde(default)]
+	free_list_order: FreeListOrder,
+	#[serde(default)]
+	generation_overflow: GenerationOverflow,
+	#[serde(skip)]
+	reserved: AtomicUsize,
+}
+
+impl Clone for HandleAllocator {
+	fn clone(&self) -> Self {
+		Self {
+			allocations: self.allocations.clone(),
+			available_handles: self.available_handles.clone(),
+			free_list_order: self.free_list_order,
+			generation_overflow: self.generation_overflow,
+			reserved: AtomicUsize::new(self.reserved_count()),
+		}
+	}
+}
+
+impl Hash for HandleAllocator {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.allocations.hash(state);
+		self.available_handles.hash(state);
+		self.reserved_count().hash(state);
+	}
 }
 
 impl HandleAllocator {
@@ -171,40 +271,168 @@ impl HandleAllocator {
 		Self::default()
 	}
 
+	pub const fn with_free_list_order(mut self, free_list_order: FreeListOrder) -> Self {
+		self.free_list_order = free_list_order;
+		self
+	}
+
+	pub const fn with_generation_overflow(mut self, generation_overflow: GenerationOverflow) -> Self {
+		self.generation_overflow = generation_overflow;
+		self
+	}
+
+	pub const fn free_list_order(&self) -> FreeListOrder {
+		self.free_list_order
+	}
+
+	pub const fn generation_overflow(&self) -> GenerationOverflow {
+		self.generation_overflow
+	}
+
 	pub fn allocate(&mut self) -> Handle {
-		match self.available_handles.pop() {
+		self.flush();
+		self.allocate_unreserved()
+	}
+
+	/// Hand out a handle through a shared reference. The handle is not allocated until the next
+	/// call to `flush`, or to any method that needs mutable access.
+	///
+	/// Reservations map onto the handles that the following calls to `allocate` would return, so
+	/// reserved handles never collide with handles allocated later.
+	pub fn reserve_handle(&self) -> Handle {
+		let reservation = self.reserved.fetch_add(1, Ordering::Relaxed);
+		let available = self.available_handles.len();
+		if reservation < available {
+			let index = match self.free_list_order {
+				FreeListOrder::Lifo => self.available_handles[available - 1 - reservation],
+				FreeListOrder::Fifo => self.available_handles[reservation],
+			};
+			Handle {
+				index,
+				generation: self.next_generation(index),
+			}
+		} else {
+			Handle {
+				index: Self::new_index(self.allocations.len() + reservation - available),
+				generation: 0,
+			}
+		}
+	}
+
+	/// The number of handles reserved since the last flush.
+	pub fn reserved_count(&self) -> usize {
+		self.reserved.load(Ordering::Relaxed)
+	}
+
+	/// Allocate every reserved handle, returning them in the order they were reserved.
+	pub fn flush(&mut self) -> Vec<Handle> {
+		let reserved = std::mem::take(self.reserved.get_mut());
+		(0..reserved).map(|_| self.allocate_unreserved()).collect()
+	}
+
+	fn allocate_unreserved(&mut self) -> Handle {
+		let index = match self.free_list_order {
+			FreeListOrder::Lifo => self.available_handles.pop_back(),
+			FreeListOrder::Fifo => self.available_handles.pop_front(),
+		};
+		match index {
 			Some(index) => {
-				self.allocations[index].generation += 1;
-				self.allocations[index].allocated = true;
-				Handle {
-					index,
-					generation: self.allocations[index].generation,
-				}
+				let generation = self.next_generation(index);
+				let allocation = &mut self.allocations[index as usize];
+				allocation.generation = generation;
+				allocation.allocated = true;
+				allocation.disabled = false;
+				Handle { index, generation }
 			},
 			None => {
-				self.allocations.push(Allocation { allocated: true, generation: 0 });
-				Handle {
-					index: self.allocations.len() - 1,
+				let index = Self::new_index(self.allocations.len());
+				self.allocations.push(Allocation {
+					allocated: true,
 					generation: 0,
-				}
+					disabled: false,
+				});
+				Handle { index, generation: 0 }
 			},
 		}
 	}
 
+	fn next_generation(&self, index: u32) -> u32 {
+		self.allocations[index as usize].generation.wrapping_add(1)
+	}
+
+	fn new_index(index: usize) -> u32 {
+		u32::try_from(index).expect("Handle indices are exhausted")
+	}
+
+	/// Reserve capacity for at least `additional` more allocations.
+	pub fn reserve(&mut self, additional: usize) {
+		self.flush();
+		self.allocations.reserve(additional.saturating_sub(self.available_handles.len()));
+	}
+
+	/// Release a handle. Its index is made available again unless its generation is exhausted and
+	/// the allocator retires exhausted indices.
 	pub fn deallocate(&mut self, handle: &Handle) {
+		self.flush();
 		if !self.is_allocated(handle) {
 			return;
 		}
-		self.allocations[handle.index].allocated = false;
-		self.available_handles.push(handle.index);
+		self.allocations[handle.slot()].allocated = false;
+		self.allocations[handle.slot()].disabled = false;
+		if handle.generation == u32::MAX && self.generation_overflow == GenerationOverflow::Retire {
+			return;
+		}
+		self.available_handles.push_back(handle.index);
 	}
 
 	pub fn is_allocated(&self, handle: &Handle) -> bool {
-		self.handle_exists(handle) && self.allocations[handle.index].generation == handle.generation && self.allocations[handle.index].allocated
+		self.handle_exists(handle) && self.allocations[handle.slot()].generation == handle.generation && self.allocations[handle.slot()].allocated
+	}
+
+	/// Mark an allocated handle as disabled or enabled again. Returns false if the handle is not
+	/// allocated.
+	pub fn set_disabled(&mut self, handle: &Handle, disabled: bool) -> bool {
+		self.flush();
+		if !self.is_allocated(handle) {
+			return false;
+		}
+		self.allocations[handle.slot()].disabled = disabled;
+		true
+	}
+
+	pub fn is_disabled(&self, handle: &Handle) -> bool {
+		self.is_allocated(handle) && self.allocations[handle.slot()].disabled
+	}
+
+	/// Whether the handle currently allocated at `index` is disabled, for iterating over component
+	/// columns by index.
+	pub fn is_index_disabled(&self, index: usize) -> bool {
+		self.allocations.get(index).is_some_and(|allocation| allocation.allocated && allocation.disabled)
 	}
 
-	pub fn handle_exists(&self, handle: &Handle) -> bool {
-		handle.index < self.allocations.len()
+	/// The handle currently allocated at `index`, if any.
+	pub fn handle_at(&self, index: usize) -> Option<Handle> {
+		self.allocations.get(index).filter(|allocation| allocation.allocated).map(|allocation| Handle {
+			index: Self::new_index(index),
+			generation: allocation.generation,
+		})
+	}
+
+	pub const fn handle_exists(&self, handle: &Handle) -> bool {
+		handle.slot() < self.allocations.len()
+	}
+
+	/// The number of indices that will never be handed out again because their generation is
+	/// exhausted.
+	pub fn retired_count(&self) -> usize {
+		match self.generation_overflow {
+			GenerationOverflow::Retire => self
+				.allocations
+				.iter()
+				.filter(|allocation| !allocation.allocated && allocation.generation == u32::MAX)
+				.count(),
+			GenerationOverflow::Wrap => 0,
+		}
 	}
 
 	pub fn allocated_handles(&self) -> Vec<Handle> {
@@ -213,7 +441,7 @@ impl HandleAllocator {
 			.enumerate()
 			.filter(|(_, allocation)| allocation.allocated)
 			.map(|(index, allocation)| Handle {
-				index,
+				index: Self::new_index(index),
 				generation: allocation.generation,
 			})
 			.collect()
@@ -276,4 +504,76 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn reserved_handles() {
+		let mut handle_allocator = HandleAllocator::new();
+		let first_handle = handle_allocator.allocate();
+		handle_allocator.deallocate(&first_handle);
+
+		let reused_handle = handle_allocator.reserve_handle();
+		let new_handle = handle_allocator.reserve_handle();
+		assert_eq!(reused_handle, Handle { index: 0, generation: 1 });
+		assert_eq!(new_handle, Handle { index: 1, generation: 0 });
+		assert!(!handle_allocator.is_allocated(&reused_handle));
+
+		assert_eq!(handle_allocator.flush(), vec![reused_handle, new_handle]);
+		assert!(handle_allocator.is_allocated(&reused_handle));
+		assert!(handle_allocator.is_allocated(&new_handle));
+		assert_eq!(handle_allocator.reserved_count(), 0);
+
+		let reserved_handle = handle_allocator.reserve_handle();
+		let allocated_handle = handle_allocator.allocate();
+		assert_ne!(reserved_handle, allocated_handle);
+		assert!(handle_allocator.is_allocated(&reserved_handle));
+	}
+
+	#[test]
+	fn packed_handles() {
+		let handle = Handle::new(7, 3);
+		assert_eq!(handle.to_bits(), (3 << 32) | 7);
+		assert_eq!(Handle::from_bits(handle.to_bits()), handle);
+	}
+
+	#[test]
+	fn fifo_free_list() {
+		let mut handle_allocator = HandleAllocator::new().with_free_list_order(FreeListOrder::Fifo);
+		let first_handle = handle_allocator.allocate();
+		let second_handle = handle_allocator.allocate();
+		handle_allocator.deallocate(&first_handle);
+		handle_allocator.deallocate(&second_handle);
+
+		assert_eq!(handle_allocator.reserve_handle(), Handle::new(0, 1));
+		assert_eq!(handle_allocator.allocate(), Handle::new(1, 1));
+
+		let mut handle_allocator = HandleAllocator::new();
+		let first_handle = handle_allocator.allocate();
+		let second_handle = handle_allocator.allocate();
+		handle_allocator.deallocate(&first_handle);
+		handle_allocator.deallocate(&second_handle);
+
+		assert_eq!(handle_allocator.allocate(), Handle::new(1, 1));
+	}
+
+	#[test]
+	fn generation_overflow() {
+		let exhausted = || HandleAllocator {
+			allocations: vec![Allocation {
+				allocated: true,
+				generation: u32::MAX,
+				disabled: false,
+			}],
+			..HandleAllocator::default()
+		};
+
+		let mut handle_allocator = exhausted();
+		handle_allocator.deallocate(&Handle::new(0, u32::MAX));
+		assert_eq!(handle_allocator.retired_count(), 1);
+		assert_eq!(handle_allocator.allocate(), Handle::new(1, 0));
+
+		let mut handle_allocator = exhausted().with_generation_overflow(GenerationOverflow::Wrap);
+		handle_allocator.deallocate(&Handle::new(0, u32::MAX));
+		assert_eq!(handle_allocator.retired_count(), 0);
+		assert_eq!(handle_allocator.allocate(), Handle::new(0, 0));
+	}
 }
@@ -1,16 +1,38 @@
+use self::error::NotCloneableError;
 use crate::{
+	command::CommandBuffer,
+	component_id::{ComponentId, ComponentIds, ComponentLayout, DynamicComponent},
 	error::Result,
+	name::{DuplicateNamePolicy, Name},
+	registry::TypeRegistry,
 	resource::ResourceMap,
-	vec::{error::HandleNotFoundError, GenerationalVec, Handle, HandleAllocator, SlotVec},
+	schedule::SystemRun,
+	stable_id::StableId,
+	vec::{error::HandleNotFoundError, GenerationalVec, Handle, HandleAllocator, Slot, SlotVec},
 };
 use std::{
 	any::TypeId,
-	cell::{Ref, RefCell, RefMut},
+	cell::{Cell, Ref, RefCell, RefMut},
 	collections::HashMap,
 	ops::Deref,
 	rc::Rc,
 };
 
+pub mod error {
+	#[derive(Debug)]
+	pub struct NotCloneableError {
+		pub type_names: Vec<&'static str>,
+	}
+
+	impl std::error::Error for NotCloneableError {}
+
+	impl std::fmt::Display for NotCloneableError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			write!(f, "Components are not registered as cloneable: {}", self.type_names.join(", "))
+		}
+	}
+}
+
 /*
 	Entities:                    Entity 0                       Entity 1   Entity 2                         Entity 3
 	Physics Components   -> Vec( Some(Physics { vel: 3 }),      None,      None,                            Some(Physics { vel: 04 }) )
@@ -88,15 +110,24 @@ macro_rules! izip {
 
 // TODO: make systems accessing unregistered components recoverable (maybe by auto registering
 // types)
+//
+// Systems skip disabled entities, unless `include_disabled` is passed after the function name:
+// system!(my_system, include_disabled, [resources, entity], (), (position: Position) -> Result<()>
+// { ... });
+//
+// The generated function takes `&World`, the access a `Schedule` gives its systems, so it can be
+// added to a schedule as is. Callers holding a `&mut World` can still pass it, reborrowed as
+// `&world`.
 #[macro_export]
 macro_rules! system {
-	($fn:tt, [$resources:ident, $entity:ident], ($($arg:ident: $arg_type:ty),*), ($component_name:ident: $component_type:ty) -> $result:ty {$($body:tt)*}) => {
-		pub fn $fn($($arg: $arg_type,)* world: &mut World) -> $result {
+	(@system $include_disabled:literal, $fn:tt, [$resources:ident, $entity:ident], ($($arg:ident: $arg_type:ty),*), ($component_name:ident: $component_type:ty) -> $result:ty {$($body:tt)*}) => {
+		pub fn $fn($($arg: $arg_type,)* world: &World) -> $result {
 			world
 				.get_component_vec_mut::<$component_type>()
 				.unwrap_or_else(|| panic!("System accessed an unregistered component type: {:?}", stringify!($component_type)))
 				.iter_mut()
 				.enumerate()
+				.filter(|(entity, _)| $include_disabled || world.is_index_enabled(*entity))
 				.filter_map(|(entity, $component_name)| match ($component_name) {
 					Some($component_name) => {
 						let $component_name = $component_name.downcast_mut::<$component_type>().unwrap();
@@ -104,20 +135,21 @@ macro_rules! system {
 					},
 					_ => None,
 				})
-				.try_for_each(|($resources, $entity, mut $component_name)| {
-					$($body)*
+				.try_for_each(|($resources, $entity, $component_name)| {
+					world.run_for_entity($entity, || -> $result { $($body)* })
 				})
 		}
     };
 
-    ($fn:tt, [$resources:ident, $entity:ident], ($($arg:ident: $arg_type:ty),*), ($($component_name:ident: $component_type:ty),*) -> $result:ty {$($body:tt)*}) => {
-		pub fn $fn($($arg: $arg_type,)* world: &mut World) -> $result {
+    (@system $include_disabled:literal, $fn:tt, [$resources:ident, $entity:ident], ($($arg:ident: $arg_type:ty),*), ($($component_name:ident: $component_type:ty),*) -> $result:ty {$($body:tt)*}) => {
+		pub fn $fn($($arg: $arg_type,)* world: &World) -> $result {
 			izip!(
 				$(
 					world.get_component_vec_mut::<$component_type>().unwrap_or_else(|| panic!("System accessed an unregistered component type: {:?}", stringify!($component_type))).iter_mut()
 				),*
 			)
 			.enumerate()
+			.filter(|(entity, _)| $include_disabled || world.is_index_enabled(*entity))
 			.filter_map(|(entity, ($($component_name),*))| match ($($component_name,)*) {
 				($(Some($component_name),)*) => {
 					$(
@@ -128,17 +160,35 @@ macro_rules! system {
 				_ => None,
 			})
 			.try_for_each(|($resources, $entity, $($component_name,)*)| {
-				$($body)*
+				world.run_for_entity($entity, || -> $result { $($body)* })
 			})
 		}
-    }
+    };
+
+	($fn:tt, include_disabled, $($rest:tt)*) => {
+		$crate::system!(@system true, $fn, $($rest)*);
+	};
+
+	($fn:tt, $($rest:tt)*) => {
+		$crate::system!(@system false, $fn, $($rest)*);
+	};
 }
 
 #[derive(Default)]
 pub struct World {
-	resources: Rc<RefCell<ResourceMap>>,
-	components: ComponentMap,
-	allocator: HandleAllocator,
+	pub(crate) resources: Rc<RefCell<ResourceMap>>,
+	pub(crate) components: ComponentMap,
+	pub(crate) allocator: HandleAllocator,
+	pub(crate) registry: TypeRegistry,
+	pub(crate) commands: RefCell<CommandBuffer>,
+	pub(crate) stable_ids: RefCell<HashMap<StableId, Entity>>,
+	pub(crate) names: RefCell<HashMap<String, Vec<Entity>>>,
+	pub(crate) stale_names: RefCell<HashMap<Entity, Option<String>>>,
+	pub(crate) duplicate_name_policy: DuplicateNamePolicy,
+	pub(crate) component_ids: ComponentIds,
+	pub(crate) dynamic_components: HashMap<ComponentId, ComponentVecHandle>,
+	pub(crate) system_run: RefCell<SystemRun>,
+	pub(crate) stale_indices: Cell<bool>,
 }
 
 impl World {
@@ -146,6 +196,16 @@ impl World {
 		Self::default()
 	}
 
+	/// Create a world that allocates entities with `allocator`, for example to configure its free
+	/// list order or generation overflow policy.
+	pub fn with_allocator(allocator: HandleAllocator) -> Self {
+		Self { allocator, ..Self::default() }
+	}
+
+	pub const fn allocator(&self) -> &HandleAllocator {
+		&self.allocator
+	}
+
 	pub const fn resources(&self) -> &Rc<RefCell<ResourceMap>> {
 		&self.resources
 	}
@@ -155,15 +215,47 @@ impl World {
 	}
 
 	pub fn create_entities(&mut self, count: usize) -> Vec<Entity> {
-		(0..count).into_iter().map(|_index| self.allocator.allocate()).collect()
+		self.allocate_reserved_entities();
+		(0..count)
+			.map(|_index| {
+				let entity = self.allocator.allocate();
+				trace_event!(entity.index = *entity.index(), entity.generation = *entity.generation(), "entity spawned");
+				entity
+			})
+			.collect()
+	}
+
+	/// Allocate the entities reserved through `reserve_entity`. Called before any allocator method
+	/// that would allocate them implicitly, so that every allocated entity emits a spawn event.
+	pub(crate) fn allocate_reserved_entities(&mut self) -> Vec<Entity> {
+		let entities = self.allocator.flush();
+		#[cfg(feature = "tracing")]
+		for entity in entities.iter() {
+			trace_event!(entity.index = *entity.index(), entity.generation = *entity.generation(), "entity spawned");
+		}
+		entities
 	}
 
 	pub fn remove_entity(&mut self, entity: Entity) {
 		self.remove_entities(&[entity]);
 	}
 
+	/// Remove entities and drop their components, so that a reused index never observes the
+	/// components of a previous generation.
 	pub fn remove_entities(&mut self, entities: &[Entity]) {
-		entities.iter().for_each(|entity| self.allocator.deallocate(entity))
+		self.allocate_reserved_entities();
+		for entity in entities.iter() {
+			if !self.allocator.is_allocated(entity) {
+				continue;
+			}
+			self.unindex_entity(*entity);
+			self.components
+				.values()
+				.chain(self.dynamic_components.values())
+				.for_each(|component_vec| component_vec.borrow_mut().remove(*entity));
+			self.allocator.deallocate(entity);
+			trace_event!(entity.index = *entity.index(), entity.generation = *entity.generation(), "entity despawned");
+		}
 	}
 
 	pub fn add_component<T: 'static>(&mut self, entity: Entity, component: T) -> Result<()> {
@@ -179,30 +271,133 @@ impl World {
 	}
 
 	fn assign_component<T: 'static>(&mut self, entity: Entity, value: Option<Component>) -> Result<()> {
+		self.registry.component_entry::<T>();
+		self.assign_component_by_id(entity, TypeId::of::<T>(), value)
+	}
+
+	pub(crate) fn assign_component_by_id(&mut self, entity: Entity, type_id: TypeId, value: Option<Component>) -> Result<()> {
+		let components = self.prepare_component(entity, type_id, value.as_ref())?;
+		self.store_component(&components, entity, type_id, value);
+		Ok(())
+	}
+
+	/// Check that `value` can be assigned to `entity` and update the indices for it, without moving
+	/// it into its column yet, so that callers still own the component if this fails.
+	pub(crate) fn prepare_component(&mut self, entity: Entity, type_id: TypeId, value: Option<&Component>) -> Result<ComponentVecHandle> {
 		if !self.allocator.handle_exists(&entity) {
 			return Err(Box::new(HandleNotFoundError { handle: entity }) as Box<dyn std::error::Error>);
 		}
 
-		let mut components = self
+		let components = self
 			.components
-			.entry(TypeId::of::<T>())
+			.entry(type_id)
 			.or_insert_with(|| Rc::new(RefCell::new(ComponentVec::default())))
-			.borrow_mut();
+			.clone();
+		if value.is_some() {
+			components.borrow().check_generation(entity)?;
+		}
+		self.index_component(entity, type_id, value)?;
+		Ok(components)
+	}
 
+	/// Move `value` into the column returned by `prepare_component`.
+	pub(crate) fn store_component(&self, components: &ComponentVecHandle, entity: Entity, type_id: TypeId, value: Option<Component>) {
+		let mut components = components.borrow_mut();
+		#[cfg(feature = "tracing")]
+		let type_name = self.registry.component(&type_id).map(|registration| registration.name()).unwrap_or_default();
+		#[cfg(not(feature = "tracing"))]
+		let _ = type_id;
 		match value {
 			Some(component) => {
-				components.insert(entity, component)?;
+				components.insert(entity, component).expect("The generation was checked by prepare_component");
+				trace_event!(
+					entity.index = *entity.index(),
+					entity.generation = *entity.generation(),
+					component = type_name,
+					"component added"
+				);
 			},
 			None => {
 				components.remove(entity);
+				trace_event!(
+					entity.index = *entity.index(),
+					entity.generation = *entity.generation(),
+					component = type_name,
+					"component removed"
+				);
 			},
 		}
+	}
 
-		Ok(())
+	/// Move the component of type `type_id` out of `entity`, updating the indices and emitting
+	/// events like `remove_component`.
+	pub(crate) fn take_component_by_id(&mut self, entity: Entity, type_id: TypeId) -> Option<Component> {
+		let components = self.components.get(&type_id)?.clone();
+		if !self.entity_exists(entity) || components.borrow().get(entity).is_none() {
+			return None;
+		}
+		self.index_component(entity, type_id, None).ok()?;
+		let component = components.borrow_mut().take(entity);
+		trace_event!(
+			entity.index = *entity.index(),
+			entity.generation = *entity.generation(),
+			component = self.registry.component(&type_id).map(|registration| registration.name()).unwrap_or_default(),
+			"component removed"
+		);
+		component
+	}
+
+	/// Update the world-level indices of components such as `StableId` and `Name` before `value`
+	/// is assigned to `entity`.
+	pub(crate) fn index_component(&mut self, entity: Entity, type_id: TypeId, value: Option<&Component>) -> Result<()> {
+		self.refresh_indices();
+		self.index_stable_id(entity, type_id, value)?;
+		self.index_name(entity, type_id, value)
+	}
+
+	fn unindex_entity(&mut self, entity: Entity) {
+		self.refresh_indices();
+		self.unindex_stable_id(entity);
+		self.unindex_name(entity);
+	}
+
+	/// Mark the indices as stale when the column of an indexed component is borrowed mutably, since
+	/// its values can then change without going through `assign_component_by_id`.
+	pub(crate) fn mark_column_borrowed(&self, type_id: TypeId) {
+		if type_id == TypeId::of::<StableId>() || type_id == TypeId::of::<Name>() {
+			self.stale_indices.set(true);
+		}
+	}
+
+	/// Like `mark_column_borrowed`, for a borrow of the component of a single entity. A borrowed
+	/// `Name` only re-indexes that entity.
+	pub(crate) fn mark_component_borrowed(&self, entity: Entity, type_id: TypeId) {
+		if type_id == TypeId::of::<Name>() {
+			self.mark_name_borrowed(entity);
+		} else {
+			self.mark_column_borrowed(type_id);
+		}
+	}
+
+	/// Rebuild the indices from their columns if they are stale. While an indexed column is
+	/// borrowed mutably it cannot be read, and the indices stay stale until the next call.
+	pub(crate) fn refresh_indices(&self) {
+		if self.stale_indices.get() && self.rebuild_stable_ids() && self.rebuild_names() {
+			self.stale_indices.set(false);
+		}
+		if !self.stale_indices.get() {
+			self.reindex_stale_names();
+		}
+	}
+
+	/// Rebuild the indices after the columns were replaced wholesale.
+	pub(crate) fn rebuild_indices(&self) {
+		self.stale_indices.set(true);
+		self.refresh_indices();
 	}
 
 	#[must_use]
-	pub fn get_component<T: 'static>(&self, entity: Entity) -> Option<Ref<T>> {
+	pub fn get_component<T: 'static>(&self, entity: Entity) -> Option<Ref<'_, T>> {
 		if !self.entity_exists(entity) {
 			return None;
 		}
@@ -217,7 +412,7 @@ impl World {
 	}
 
 	#[must_use]
-	pub fn get_component_mut<T: 'static>(&self, entity: Entity) -> Option<RefMut<T>> {
+	pub fn get_component_mut<T: 'static>(&self, entity: Entity) -> Option<RefMut<'_, T>> {
 		if !self.entity_exists(entity) {
 			return None;
 		}
@@ -225,27 +420,235 @@ impl World {
 			if !entity_has_component(entity, component_vec) {
 				return None;
 			}
+			self.mark_component_borrowed(entity, TypeId::of::<T>());
 			Some(RefMut::map(component_vec.borrow_mut(), |t| {
 				t.get_mut(entity).and_then(|c| c.downcast_mut::<T>()).unwrap()
 			}))
 		})
 	}
 
-	pub fn get_component_vec<T: 'static>(&self) -> Option<Ref<ComponentVec>> {
+	pub fn get_component_vec<T: 'static>(&self) -> Option<Ref<'_, ComponentVec>> {
 		self.components.get(&TypeId::of::<T>()).map(|component_vec| component_vec.deref().borrow())
 	}
 
-	pub fn get_component_vec_mut<T: 'static>(&self) -> Option<RefMut<ComponentVec>> {
+	pub fn get_component_vec_mut<T: 'static>(&self) -> Option<RefMut<'_, ComponentVec>> {
+		self.mark_column_borrowed(TypeId::of::<T>());
 		self.components.get(&TypeId::of::<T>()).map(|component_vec| component_vec.deref().borrow_mut())
 	}
 
 	pub fn register_component<T: 'static>(&mut self) {
-		self.components.entry(TypeId::of::<T>()).or_insert(component_vec!());
+		self.registry.component_entry::<T>();
+		self.components.entry(TypeId::of::<T>()).or_insert_with(|| component_vec!());
+	}
+
+	pub fn register_cloneable_component<T: Clone + 'static>(&mut self) {
+		self.register_component::<T>();
+		self.registry.register_cloneable_component::<T>();
+	}
+
+	/// Include the component type `T` in `state_hash`. `name` orders its column in the digest, so
+	/// it must be the same, and unique, in every build that compares hashes.
+	pub fn register_hashable_component<T: std::hash::Hash + 'static>(&mut self, name: &'static str) {
+		self.register_component::<T>();
+		self.registry.register_hashable_component::<T>(name);
+	}
+
+	pub fn register_serializable_component<T: serde::Serialize + serde::de::DeserializeOwned + 'static>(&mut self, name: &'static str) {
+		self.register_component::<T>();
+		self.registry.register_serializable_component::<T>(name);
+	}
+
+	pub fn register_map_entities<T: crate::scene::MapEntities + 'static>(&mut self) {
+		self.register_component::<T>();
+		self.registry.register_map_entities::<T>();
+	}
+
+	pub fn register_cloneable_resource<T: Clone + 'static>(&mut self) {
+		self.registry.register_cloneable_resource::<T>();
+	}
+
+	pub const fn registry(&self) -> &TypeRegistry {
+		&self.registry
 	}
 
 	pub fn entity_exists(&self, entity: Entity) -> bool {
 		self.allocator.is_allocated(&entity)
 	}
+
+	/// Exclude `entity` from systems without removing its components.
+	pub fn disable(&mut self, entity: Entity) -> Result<()> {
+		self.set_disabled(entity, true)
+	}
+
+	pub fn enable(&mut self, entity: Entity) -> Result<()> {
+		self.set_disabled(entity, false)
+	}
+
+	fn set_disabled(&mut self, entity: Entity, disabled: bool) -> Result<()> {
+		self.allocate_reserved_entities();
+		if !self.allocator.set_disabled(&entity, disabled) {
+			return Err(Box::new(HandleNotFoundError { handle: entity }));
+		}
+		Ok(())
+	}
+
+	/// Whether `entity` exists and is not disabled.
+	pub fn is_enabled(&self, entity: Entity) -> bool {
+		self.entity_exists(entity) && !self.allocator.is_disabled(&entity)
+	}
+
+	/// Whether the entity at `index` is not disabled, used by systems iterating component columns.
+	pub fn is_index_enabled(&self, index: usize) -> bool {
+		!self.allocator.is_index_disabled(index)
+	}
+
+	/// Every existing entity that is not disabled.
+	pub fn enabled_entities(&self) -> Vec<Entity> {
+		self.allocator
+			.allocated_handles()
+			.into_iter()
+			.filter(|entity| !self.allocator.is_disabled(entity))
+			.collect()
+	}
+
+	/// Create a new entity holding a copy of every component of `entity`, including runtime
+	/// components. The `StableId` is not copied, since it must stay unique.
+	pub fn clone_entity(&mut self, entity: Entity) -> Result<Entity> {
+		let components = self.clone_components(entity, &[TypeId::of::<StableId>()])?;
+		let dynamic = self.clone_dynamic_components_of(entity);
+		let clone = self.create_entity();
+		if let Err(error) = self.insert_clone(clone, components, dynamic) {
+			self.remove_entity(clone);
+			return Err(error);
+		}
+		Ok(clone)
+	}
+
+	/// Create a new entity in `world` holding a copy of every component of `entity`. Runtime
+	/// components are matched by name and registered in `world` if needed. Fails without creating
+	/// an entity if a copied `StableId` or `Name` conflicts with an entity of `world`.
+	pub fn clone_entity_into(&self, entity: Entity, world: &mut Self) -> Result<Entity> {
+		let components = self.clone_components(entity, &[])?;
+		let dynamic = self.clone_dynamic_components_of(entity);
+		for (type_id, _) in components.iter() {
+			if let Some(registration) = self.registry.component(type_id) {
+				world.registry.merge_component_registration(*type_id, *registration);
+			}
+		}
+		let clone = world.create_entity();
+		if let Err(error) = world.insert_clone(clone, components, dynamic) {
+			world.remove_entity(clone);
+			return Err(error);
+		}
+		Ok(clone)
+	}
+
+	fn insert_clone(&mut self, clone: Entity, components: Vec<(TypeId, Component)>, dynamic: Vec<(String, ComponentLayout, DynamicComponent)>) -> Result<()> {
+		for (type_id, component) in components {
+			self.assign_component_by_id(clone, type_id, Some(component))?;
+		}
+		for (name, layout, component) in dynamic {
+			let id = match self.component_ids.by_name(&name) {
+				Some(id) => id,
+				None => self.register_dynamic_component(name, layout)?,
+			};
+			self.add_dynamic_component(clone, id, component)?;
+		}
+		Ok(())
+	}
+
+	/// Fork the world, copying every entity, every component and every resource registered as
+	/// cloneable. Resources that are not registered as cloneable are not carried over.
+	pub fn try_clone(&self) -> Result<Self, NotCloneableError> {
+		let mut type_names = self
+			.components
+			.iter()
+			.filter(|(_, component_vec)| component_vec.borrow().iter().any(Option::is_some))
+			.filter_map(|(type_id, _)| self.registry.component(type_id))
+			.filter(|registration| !registration.is_cloneable())
+			.map(|registration| registration.name())
+			.collect::<Vec<_>>();
+		if !type_names.is_empty() {
+			type_names.sort_unstable();
+			return Err(NotCloneableError { type_names });
+		}
+
+		let components = self
+			.components
+			.iter()
+			.map(|(type_id, component_vec)| {
+				let column = self.clone_component_vec(type_id, &component_vec.borrow()).unwrap_or_default();
+				(*type_id, Rc::new(RefCell::new(column)))
+			})
+			.collect();
+
+		let mut resources = ResourceMap::new();
+		{
+			let resource_map = self.resources.borrow();
+			self.registry.resource_types().for_each(|type_id| {
+				if let Some(resource) = resource_map.get_by_id(type_id).and_then(|resource| self.registry.clone_resource(type_id, resource)) {
+					resources.insert_by_id(*type_id, resource);
+				}
+			});
+		}
+
+		Ok(Self {
+			resources: Rc::new(RefCell::new(resources)),
+			components,
+			allocator: self.allocator.clone(),
+			registry: self.registry.clone(),
+			commands: RefCell::default(),
+			system_run: RefCell::default(),
+			stable_ids: self.stable_ids.clone(),
+			stale_indices: self.stale_indices.clone(),
+			names: self.names.clone(),
+			stale_names: self.stale_names.clone(),
+			duplicate_name_policy: self.duplicate_name_policy,
+			component_ids: self.component_ids.clone(),
+			dynamic_components: self.clone_dynamic_components(),
+		})
+	}
+
+	fn clone_components(&self, entity: Entity, skip: &[TypeId]) -> Result<Vec<(TypeId, Component)>> {
+		if !self.entity_exists(entity) {
+			return Err(Box::new(HandleNotFoundError { handle: entity }));
+		}
+
+		let mut type_names = Vec::new();
+		let mut components = Vec::new();
+		for (type_id, component_vec) in self.components.iter().filter(|(type_id, _)| !skip.contains(type_id)) {
+			let component_vec = component_vec.borrow();
+			let Some(component) = component_vec.get(entity) else {
+				continue;
+			};
+			match self.registry.clone_component(type_id, component) {
+				Some(component) => components.push((*type_id, component)),
+				None => type_names.push(self.registry.component(type_id).map_or("<unknown>", |registration| registration.name())),
+			}
+		}
+
+		if !type_names.is_empty() {
+			type_names.sort_unstable();
+			return Err(Box::new(NotCloneableError { type_names }));
+		}
+
+		Ok(components)
+	}
+
+	/// Copy a component column, if its type was registered as cloneable.
+	pub(crate) fn clone_component_vec(&self, type_id: &TypeId, component_vec: &ComponentVec) -> Option<ComponentVec> {
+		if !self.registry.component(type_id).is_some_and(|registration| registration.is_cloneable()) {
+			return None;
+		}
+		let elements = component_vec
+			.iter()
+			.map(|slot| {
+				slot.as_ref()
+					.and_then(|slot| self.registry.clone_component(type_id, slot).map(|component| Slot::new(component, *slot.generation())))
+			})
+			.collect();
+		Some(ComponentVec::new(elements))
+	}
 }
 
 pub fn entity_has_component(entity: Entity, components: &ComponentVecHandle) -> bool {
@@ -268,9 +671,6 @@ mod tests {
 		value: u8,
 	}
 
-	#[allow(dead_code)]
-	struct Name(String);
-
 	// Translate only named entities
 	system!(translation_system, [_resources, _entity], (value: f32), (position: Position, _name: Name, _health: Health) -> Result<()> {
 		position.x += value;
@@ -287,6 +687,16 @@ mod tests {
 		Ok(())
 	});
 
+	system!(heal_system, [_resources, _entity], (), (health: Health) -> Result<()> {
+		health.value += 1;
+		Ok(())
+	});
+
+	system!(heal_all_system, include_disabled, [_resources, _entity], (), (health: Health, _position: Position) -> Result<()> {
+		health.value += 1;
+		Ok(())
+	});
+
 	#[test]
 	fn entity() -> Result<()> {
 		let mut world = World::default();
@@ -298,6 +708,50 @@ mod tests {
 		Ok(())
 	}
 
+	#[test]
+	fn disabled_entities_are_skipped() -> Result<()> {
+		let mut world = World::new();
+		let entities = world.create_entities(2);
+		for entity in entities.iter() {
+			world.add_component(*entity, Health::default())?;
+			world.add_component(*entity, Position::default())?;
+		}
+		world.disable(entities[1])?;
+		assert!(!world.is_enabled(entities[1]));
+		assert_eq!(world.enabled_entities(), vec![entities[0]]);
+
+		heal_system(&world)?;
+		assert_eq!(world.get_component::<Health>(entities[0]).as_deref(), Some(&Health { value: 1 }));
+		assert_eq!(world.get_component::<Health>(entities[1]).as_deref(), Some(&Health { value: 0 }));
+
+		heal_all_system(&world)?;
+		assert_eq!(world.get_component::<Health>(entities[1]).as_deref(), Some(&Health { value: 1 }));
+
+		world.enable(entities[1])?;
+		heal_system(&world)?;
+		assert_eq!(world.get_component::<Health>(entities[1]).as_deref(), Some(&Health { value: 2 }));
+
+		world.remove_entity(entities[1]);
+		assert!(world.disable(entities[1]).is_err());
+
+		Ok(())
+	}
+
+	#[test]
+	fn removed_entity_components_are_dropped() -> Result<()> {
+		let mut world = World::new();
+		let entity = world.create_entity();
+		world.add_component(entity, Position::default())?;
+		world.remove_entity(entity);
+
+		let reused = world.create_entity();
+		assert_eq!(reused.index(), entity.index());
+		assert!(world.get_component_vec::<Position>().unwrap().iter().all(Option::is_none));
+		assert!(world.get_component::<Position>(reused).is_none());
+
+		Ok(())
+	}
+
 	#[test]
 	fn add_component() -> Result<()> {
 		let mut world = World::default();
@@ -352,7 +806,7 @@ mod tests {
 		world.add_component(entity, Health::default())?;
 		world.add_component(entity, Name("Tyrell Wellick".to_string()))?;
 
-		translation_system(10.0, &mut world)?;
+		translation_system(10.0, &world)?;
 
 		assert_eq!(world.get_component::<Position>(entity).as_deref(), Some(&Position { x: 10.0, y: 10.0 }));
 
@@ -380,7 +834,7 @@ mod tests {
 		world.add_component(entity, Position::default())?;
 
 		let value = 0.18;
-		resource_system(value, &mut world)?;
+		resource_system(value, &world)?;
 
 		assert_eq!(world.resources().borrow().get::<DeltaTime>(), Some(&DeltaTime(value)));
 
@@ -405,8 +859,8 @@ mod tests {
 	#[test]
 	#[should_panic]
 	fn system_accessed_unregistered_component() {
-		let mut world = World::new();
-		translation_system(0.14, &mut world).unwrap();
+		let world = World::new();
+		translation_system(0.14, &world).unwrap();
 	}
 
 	#[test]
@@ -422,4 +876,178 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn clone_entity() -> Result<()> {
+		let mut world = World::new();
+		world.register_cloneable_component::<Position>();
+		world.register_cloneable_component::<Health>();
+
+		let entity = world.create_entity();
+		world.add_component(entity, Position { x: 1.0, y: 2.0 })?;
+		world.add_component(entity, Health { value: 3 })?;
+
+		let clone = world.clone_entity(entity)?;
+		assert_ne!(clone, entity);
+		assert_eq!(world.get_component::<Position>(clone).as_deref(), Some(&Position { x: 1.0, y: 2.0 }));
+		assert_eq!(world.get_component::<Health>(clone).as_deref(), Some(&Health { value: 3 }));
+
+		world.add_component(entity, Name("Darlene".to_string()))?;
+		assert!(world.clone_entity(entity).is_err());
+		assert_eq!(world.allocator().allocated_handles(), vec![entity, clone]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn clone_stable_ids_and_dynamic_components() -> Result<()> {
+		let mut world = World::new();
+		world.register_cloneable_component::<StableId>();
+		let tag = world.register_dynamic_component("Tag", ComponentLayout::Bytes { size: 2 })?;
+		let entity = world.create_entity();
+		world.add_component(entity, StableId(1))?;
+		world.add_dynamic_component(entity, tag, DynamicComponent::Bytes(vec![1, 2]))?;
+
+		let clone = world.clone_entity(entity)?;
+		assert!(world.get_component::<StableId>(clone).is_none());
+		assert_eq!(world.get_dynamic_component(clone, tag).as_deref(), Some(&DynamicComponent::Bytes(vec![1, 2])));
+
+		let mut other = World::new();
+		let copy = world.clone_entity_into(entity, &mut other)?;
+		let other_tag = other.component_ids().by_name("Tag").unwrap();
+		assert_eq!(other.entity_by_stable_id(StableId(1)), Some(copy));
+		assert_eq!(other.get_dynamic_component(copy, other_tag).as_deref(), Some(&DynamicComponent::Bytes(vec![1, 2])));
+
+		assert!(world.clone_entity_into(entity, &mut other).is_err());
+		assert_eq!(other.allocator().allocated_handles(), vec![copy]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn clone_entity_into() -> Result<()> {
+		let mut world = World::new();
+		world.register_cloneable_component::<Position>();
+		let entity = world.create_entity();
+		world.add_component(entity, Position { x: 4.0, y: 5.0 })?;
+
+		let mut other = World::new();
+		let clone = world.clone_entity_into(entity, &mut other)?;
+
+		assert_eq!(other.get_component::<Position>(clone).as_deref(), Some(&Position { x: 4.0, y: 5.0 }));
+		assert!(other.registry().component(&TypeId::of::<Position>()).unwrap().is_cloneable());
+
+		Ok(())
+	}
+
+	#[test]
+	fn try_clone() -> Result<()> {
+		let mut world = World::new();
+		world.register_cloneable_component::<Position>();
+		world.register_cloneable_resource::<Health>();
+		world.resources().borrow_mut().insert(Health { value: 7 });
+		let entity = world.create_entity();
+		world.add_component(entity, Position::default())?;
+
+		let fork = world.try_clone()?;
+		fork.get_component_mut::<Position>(entity).unwrap().x = 10.0;
+		assert_eq!(world.get_component::<Position>(entity).as_deref(), Some(&Position::default()));
+		assert_eq!(fork.resources().borrow().get::<Health>(), Some(&Health { value: 7 }));
+
+		world.add_component(entity, Name("Angela Moss".to_string()))?;
+		let error = world.try_clone().err().unwrap();
+		assert_eq!(error.type_names, vec![std::any::type_name::<Name>()]);
+
+		Ok(())
+	}
+
+	#[cfg(feature = "tracing")]
+	#[test]
+	fn tracing_events() -> Result<()> {
+		use std::sync::{
+			Arc, Mutex,
+			atomic::{AtomicU64, Ordering},
+		};
+		use tracing::{
+			Event, Metadata, Subscriber,
+			field::{Field, Visit},
+			span,
+		};
+
+		#[derive(Default)]
+		struct Capture {
+			next_span: AtomicU64,
+			lines: Arc<Mutex<Vec<String>>>,
+		}
+
+		struct Message(String);
+
+		impl Visit for Message {
+			fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+				if field.name() == "message" {
+					self.0 = format!("{value:?}");
+				}
+			}
+		}
+
+		impl Subscriber for Capture {
+			fn enabled(&self, _: &Metadata<'_>) -> bool {
+				true
+			}
+
+			fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+				self.lines.lock().unwrap().push(format!("span {}", span.metadata().name()));
+				span::Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed) + 1)
+			}
+
+			fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+			fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+			fn event(&self, event: &Event<'_>) {
+				let mut message = Message(String::new());
+				event.record(&mut message);
+				self.lines.lock().unwrap().push(message.0);
+			}
+
+			fn enter(&self, _: &span::Id) {}
+
+			fn exit(&self, _: &span::Id) {}
+		}
+
+		let capture = Capture::default();
+		let lines = capture.lines.clone();
+		tracing::subscriber::with_default(capture, || -> Result<()> {
+			let mut world = World::new();
+			world.register_component::<Health>();
+			world.reserve_entity();
+			let entity = world.create_entity();
+			world.add_component(entity, Health { value: 1 })?;
+			world.remove_component::<Health>(entity)?;
+			world.spawn((Health { value: 2 },))?;
+			world.remove_entity(entity);
+
+			let mut schedule = crate::schedule::Schedule::new();
+			schedule.add_system(|_: &World| Ok(()));
+			schedule.run(&mut world)
+		})?;
+
+		assert_eq!(
+			*lines.lock().unwrap(),
+			vec![
+				"entity spawned",
+				"entity spawned",
+				"component added",
+				"component removed",
+				"entity spawned",
+				"component added",
+				"entity despawned",
+				"span schedule",
+				"span system",
+				"span flush",
+			]
+		);
+
+		Ok(())
+	}
 }